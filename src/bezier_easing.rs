@@ -1,13 +1,36 @@
+use std::{fmt, str::FromStr};
 
+use js_sys::{Array, Function};
+use wasm_bindgen::{JsCast, JsValue};
 
-const _NEWTON_ITERATIONS: u32 = 4;
-const _NEWTON_MIN_SLOPE: f64 = 0.001;
-const _SUBDIVISION_PRECISION: f64 = 0.0000001;
-const _SUBDIVISION_MAX_ITERATIONS: u32 = 10;
+const NEWTON_ITERATIONS: u32 = 4;
+const NEWTON_MIN_SLOPE: f64 = 0.001;
+const SUBDIVISION_PRECISION: f64 = 0.0000001;
+const SUBDIVISION_MAX_ITERATIONS: u32 = 10;
 
-const K_SPLINE_TABLE_SIZE: u32 = 11;
+const K_SPLINE_TABLE_SIZE: usize = 11;
 const K_SAMPLE_STEP_SIZE: f64 = 1.0 / (K_SPLINE_TABLE_SIZE as f64 - 1.0);
 
+/// Control points `bezier2` refuses to build a curve from.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BezierError {
+    /// `x1` or `x2` outside of [0, 1], the curve would not be a function of x.
+    XOutOfRange,
+    /// One of the control points is NaN or infinite.
+    NotFinite,
+}
+
+impl fmt::Display for BezierError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BezierError::XOutOfRange => f.write_str("bezier x values must be in [0, 1] range"),
+            BezierError::NotFinite => f.write_str("bezier control points must be finite numbers"),
+        }
+    }
+}
+
+impl std::error::Error for BezierError {}
+
 fn a(a_1: f64, a_2: f64) -> f64 {
     1.0 - 3.0 * a_2 + 3.0 * a_1
 }
@@ -20,39 +43,39 @@ fn c(a_1: f64) -> f64 {
     3.0 * a_1
 }
 
+/// x(t) given t, x1 and x2, or y(t) given t, y1 and y2.
 fn calc_bezier(t: f64, a_1: f64, a_2: f64) -> f64 {
     ((a(a_1, a_2) * t + b(a_1, a_2)) * t + c(a_1)) * t
 }
 
-fn _get_slope(t: f64, a_1: f64, a_2: f64) -> f64 {
+/// dx/dt given t, x1 and x2, or dy/dt given t, y1 and y2.
+fn get_slope(t: f64, a_1: f64, a_2: f64) -> f64 {
     3.0 * a(a_1, a_2) * t * t + 2.0 * b(a_1, a_2) * t + c(a_1)
 }
 
-fn _binary_subdivide(x: f64, mut a: f64, mut b: f64, m_x1: f64, m_x2: f64) -> f64 {
+fn binary_subdivide(x: f64, mut a: f64, mut b: f64, m_x1: f64, m_x2: f64) -> f64 {
     let mut current_t;
-    let mut current_x;
     let mut i = 0;
     loop {
         current_t = a + (b - a) / 2.0;
-        current_x = calc_bezier(current_t, m_x1, m_x2) - x;
+        let current_x = calc_bezier(current_t, m_x1, m_x2) - x;
         if current_x > 0.0 {
             b = current_t;
         } else {
             a = current_t;
         }
-        if (current_x.abs() <= _SUBDIVISION_PRECISION) || (i >= _SUBDIVISION_MAX_ITERATIONS) {
+        i += 1;
+        if current_x.abs() <= SUBDIVISION_PRECISION || i >= SUBDIVISION_MAX_ITERATIONS {
             break;
         }
-        i += 1;
     }
     current_t
 }
 
-
-fn _newton_raphson_iterate(x: f64, guess_t: f64, m_x1: f64, m_x2: f64) -> f64 {
+fn newton_raphson_iterate(x: f64, guess_t: f64, m_x1: f64, m_x2: f64) -> f64 {
     let mut a_guess_t = guess_t;
-    for _ in 0.._NEWTON_ITERATIONS {
-        let current_slope = _get_slope(a_guess_t, m_x1, m_x2);
+    for _ in 0..NEWTON_ITERATIONS {
+        let current_slope = get_slope(a_guess_t, m_x1, m_x2);
         if current_slope == 0.0 {
             return a_guess_t;
         }
@@ -65,41 +88,524 @@ fn _newton_raphson_iterate(x: f64, guess_t: f64, m_x1: f64, m_x2: f64) -> f64 {
 fn linear_easing(x: f64) -> f64 {
     x
 }
-pub fn bezier2(m_x1: f64, m_y1: f64, m_x2: f64, m_y2: f64) -> Box<dyn Fn(f64) -> f64> {
-    if !((0.0 <= m_x1 && m_x1 <= 1.0) && (0.0 <= m_x2 && m_x2 <= 1.0)) {
-        panic!("bezier x values must be in [0, 1] range");
+
+/// Port of `bezier-easing`: the returned function maps a progress in [0, 1] to the eased progress,
+/// like the CSS `cubic-bezier(x1, y1, x2, y2)`.
+pub fn bezier2(m_x1: f64, m_y1: f64, m_x2: f64, m_y2: f64) -> Result<Box<dyn Fn(f64) -> f64>, BezierError> {
+    if ![m_x1, m_y1, m_x2, m_y2].iter().all(|val| val.is_finite()) {
+        return Err(BezierError::NotFinite);
+    }
+    if !((0.0..=1.0).contains(&m_x1) && (0.0..=1.0).contains(&m_x2)) {
+        return Err(BezierError::XOutOfRange);
     }
 
     if (m_x1 == m_y1) && (m_x2 == m_y2) {
-        return Box::new(|x| linear_easing(x));
+        return Ok(Box::new(linear_easing));
     }
 
-    let mut sample_values = [0.0; K_SPLINE_TABLE_SIZE as usize];
-    for i in 0..K_SPLINE_TABLE_SIZE {
-        sample_values[i as usize] = calc_bezier(i as f64 * K_SAMPLE_STEP_SIZE, m_x1, m_x2);
+    let mut sample_values = [0.0; K_SPLINE_TABLE_SIZE];
+    for (i, sample) in sample_values.iter_mut().enumerate() {
+        *sample = calc_bezier(i as f64 * K_SAMPLE_STEP_SIZE, m_x1, m_x2);
     }
 
-    Box::new(move |x| {
+    let get_t_for_x = move |x: f64| {
+        let mut interval_start = 0.0;
+        let mut current_sample = 1;
+        let last_sample = K_SPLINE_TABLE_SIZE - 1;
+
+        while current_sample != last_sample && sample_values[current_sample] <= x {
+            interval_start += K_SAMPLE_STEP_SIZE;
+            current_sample += 1;
+        }
+        current_sample -= 1;
+
+        //Interpolate between the samples for an initial guess of t
+        let dist = (x - sample_values[current_sample]) / (sample_values[current_sample + 1] - sample_values[current_sample]);
+        let guess_for_t = interval_start + dist * K_SAMPLE_STEP_SIZE;
+
+        let initial_slope = get_slope(guess_for_t, m_x1, m_x2);
+        if initial_slope >= NEWTON_MIN_SLOPE {
+            newton_raphson_iterate(x, guess_for_t, m_x1, m_x2)
+        } else if initial_slope == 0.0 {
+            guess_for_t
+        } else {
+            binary_subdivide(x, interval_start, interval_start + K_SAMPLE_STEP_SIZE, m_x1, m_x2)
+        }
+    };
+
+    Ok(Box::new(move |x| {
+        //Make sure the extremes are exact
         if x == 0.0 || x == 1.0 {
             return x;
         }
+        calc_bezier(get_t_for_x(x), m_y1, m_y2)
+    }))
+}
 
-        let mut _interval_start = 0.0;
-        let mut current_sample = 1;
-        let mut last_sample = K_SPLINE_TABLE_SIZE - 1;
 
-        loop {
-            if current_sample == last_sample || x == sample_values[current_sample as usize] {
-                return sample_values[current_sample as usize];
+/// Where the jumps of `steps()` happen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepPosition {
+    Start,
+    End,
+    None,
+    Both,
+}
+
+/// Easing of `scrollTo`: a preset name, a CSS easing function or a JS function `(t) => number`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    CubicBezier([f64; 4]),
+    Steps(u32, StepPosition),
+    /// Stops of the CSS `linear()` function as `[input, output]`, inputs are sorted.
+    LinearStops(Vec<[f64; 2]>),
+    Function(Function),
+}
+
+/// Input that `Easing::parse` or `Easing::from_js` cannot turn into an easing.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EasingError {
+    /// Neither a preset nor a `cubic-bezier()`, `steps()` or `linear()` function.
+    Unknown(String),
+    /// A known function with missing or malformed arguments.
+    InvalidArguments(String),
+    Bezier(BezierError),
+}
+
+impl fmt::Display for EasingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EasingError::Unknown(easing) => write!(f, "unknown easing \"{easing}\""),
+            EasingError::InvalidArguments(easing) => write!(f, "invalid arguments in \"{easing}\""),
+            EasingError::Bezier(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for EasingError {}
+
+impl From<BezierError> for EasingError {
+    fn from(err: BezierError) -> Self {
+        EasingError::Bezier(err)
+    }
+}
+
+impl Default for Easing {
+    fn default() -> Self {
+        Easing::CubicBezier([0.25, 0.0, 0.35, 1.0])
+    }
+}
+
+impl FromStr for Easing {
+    type Err = EasingError;
+
+    fn from_str(easing: &str) -> Result<Self, Self::Err> {
+        Easing::parse(easing)
+    }
+}
+
+impl Easing {
+    /// CSS keywords and the usual `easeIn*`/`easeOut*`/`easeInOut*` presets, as cubic beziers.
+    fn preset(name: &str) -> Option<Self> {
+        let points = match name {
+            "linear" => return Some(Easing::Linear),
+            "step-start" => return Some(Easing::Steps(1, StepPosition::Start)),
+            "step-end" => return Some(Easing::Steps(1, StepPosition::End)),
+            "ease" => [0.25, 0.1, 0.25, 1.0],
+            "ease-in" => [0.42, 0.0, 1.0, 1.0],
+            "ease-out" => [0.0, 0.0, 0.58, 1.0],
+            "ease-in-out" => [0.42, 0.0, 0.58, 1.0],
+            "easeInSine" => [0.12, 0.0, 0.39, 0.0],
+            "easeOutSine" => [0.61, 1.0, 0.88, 1.0],
+            "easeInOutSine" => [0.37, 0.0, 0.63, 1.0],
+            "easeInQuad" => [0.11, 0.0, 0.5, 0.0],
+            "easeOutQuad" => [0.5, 1.0, 0.89, 1.0],
+            "easeInOutQuad" => [0.45, 0.0, 0.55, 1.0],
+            "easeInCubic" => [0.32, 0.0, 0.67, 0.0],
+            "easeOutCubic" => [0.33, 1.0, 0.68, 1.0],
+            "easeInOutCubic" => [0.65, 0.0, 0.35, 1.0],
+            "easeInQuart" => [0.5, 0.0, 0.75, 0.0],
+            "easeOutQuart" => [0.25, 1.0, 0.5, 1.0],
+            "easeInOutQuart" => [0.76, 0.0, 0.24, 1.0],
+            "easeInQuint" => [0.64, 0.0, 0.78, 0.0],
+            "easeOutQuint" => [0.22, 1.0, 0.36, 1.0],
+            "easeInOutQuint" => [0.83, 0.0, 0.17, 1.0],
+            "easeInExpo" => [0.7, 0.0, 0.84, 0.0],
+            "easeOutExpo" => [0.16, 1.0, 0.3, 1.0],
+            "easeInOutExpo" => [0.87, 0.0, 0.13, 1.0],
+            "easeInCirc" => [0.55, 0.0, 1.0, 0.45],
+            "easeOutCirc" => [0.0, 0.55, 0.45, 1.0],
+            "easeInOutCirc" => [0.85, 0.0, 0.15, 1.0],
+            "easeInBack" => [0.36, 0.0, 0.66, -0.56],
+            "easeOutBack" => [0.34, 1.56, 0.64, 1.0],
+            "easeInOutBack" => [0.68, -0.6, 0.32, 1.6],
+            _ => return None,
+        };
+        Some(Easing::CubicBezier(points))
+    }
+
+    /// Parse a preset name, `cubic-bezier(x1, y1, x2, y2)`, `steps(n, position)` or `linear(stops)`.
+    pub fn parse(easing: &str) -> Result<Self, EasingError> {
+        let easing = easing.trim();
+        if let Some(preset) = Easing::preset(easing) {
+            return Ok(preset);
+        }
+
+        let (name, args) = match easing.strip_suffix(')').and_then(|easing| easing.split_once('(')) {
+            Some((name, args)) => (name.trim(), args.split(',').map(str::trim).collect::<Vec<_>>()),
+            None => return Err(EasingError::Unknown(easing.to_string())),
+        };
+        let invalid = || EasingError::InvalidArguments(easing.to_string());
+
+        match name {
+            "cubic-bezier" => {
+                let points = args.iter().map(|arg| arg.parse::<f64>()).collect::<Result<Vec<_>, _>>().map_err(|_| invalid())?;
+                let points: [f64; 4] = points.try_into().map_err(|_| invalid())?;
+                Easing::cubic_bezier(points)
+            },
+            "steps" => {
+                let steps = args.first().and_then(|arg| arg.parse::<u32>().ok()).ok_or_else(invalid)?;
+                let position = match args.get(1..) {
+                    Some([]) => StepPosition::End,
+                    Some(["jump-start"]) | Some(["start"]) => StepPosition::Start,
+                    Some(["jump-end"]) | Some(["end"]) => StepPosition::End,
+                    Some(["jump-none"]) => StepPosition::None,
+                    Some(["jump-both"]) => StepPosition::Both,
+                    _ => return Err(invalid()),
+                };
+                let min_steps = if position == StepPosition::None { 2 } else { 1 };
+                if steps < min_steps {
+                    return Err(invalid());
+                }
+                Ok(Easing::Steps(steps, position))
+            },
+            "linear" => Easing::linear_stops(&args).ok_or_else(invalid),
+            _ => Err(EasingError::Unknown(easing.to_string())),
+        }
+    }
+
+    /// Build the easing given to `scrollTo` or to the options,
+    /// `undefined` and `null` give `None`.
+    pub fn from_js(value: &JsValue) -> Result<Option<Self>, EasingError> {
+        if value.is_undefined() || value.is_null() {
+            return Ok(None);
+        }
+        if let Some(function) = value.dyn_ref::<Function>() {
+            return Ok(Some(Easing::Function(function.clone())));
+        }
+        if let Some(easing) = value.as_string() {
+            return Easing::parse(&easing).map(Some);
+        }
+
+        let points = Array::from(value).iter().map(|point| point.as_f64()).collect::<Option<Vec<_>>>();
+        match points.and_then(|points| <[f64; 4]>::try_from(points).ok()) {
+            Some(points) => Easing::cubic_bezier(points).map(Some),
+            None => Err(EasingError::Unknown(format!("{value:?}"))),
+        }
+    }
+
+    fn cubic_bezier(points: [f64; 4]) -> Result<Self, EasingError> {
+        let _ = bezier2(points[0], points[1], points[2], points[3])?;
+        Ok(Easing::CubicBezier(points))
+    }
+
+    /// Stops of `linear()` are `<output> [<input>% [<input>%]]`. Missing inputs are spread evenly
+    /// between their neighbours, and an input lower than a previous one is raised to it.
+    fn linear_stops(args: &[&str]) -> Option<Self> {
+        let mut stops: Vec<(f64, Option<f64>)> = vec![];
+        for arg in args {
+            let mut parts = arg.split_whitespace();
+            let output = parts.next()?.parse::<f64>().ok()?;
+            let inputs = parts.map(|part| part.strip_suffix('%')?.parse::<f64>().ok().map(|input| input / 100.0)).collect::<Option<Vec<_>>>()?;
+            match inputs.as_slice() {
+                [] => stops.push((output, None)),
+                [input] => stops.push((output, Some(*input))),
+                [start, end] => {
+                    stops.push((output, Some(*start)));
+                    stops.push((output, Some(*end)));
+                },
+                _ => return None,
             }
+        }
+        if stops.len() < 2 || stops.iter().any(|(output, input)| !output.is_finite() || input.is_some_and(|input| !input.is_finite())) {
+            return None;
+        }
+
+        let last = stops.len() - 1;
+        stops[0].1.get_or_insert(0.0);
+        stops[last].1.get_or_insert(1.0);
+
+        let mut max_input = f64::MIN;
+        for stop in stops.iter_mut() {
+            if let Some(input) = stop.1.as_mut() {
+                *input = input.max(max_input);
+                max_input = *input;
+            }
+        }
 
-            if x > sample_values[current_sample as usize] {
-                _interval_start = current_sample as f64;
-                current_sample += 1;
-            } else {
-                last_sample = current_sample;
+        let mut start = 0;
+        for i in 1..stops.len() {
+            if let Some(end_input) = stops[i].1 {
+                let start_input = stops[start].1.unwrap();
+                let count = (i - start) as f64;
+                for (j, stop) in stops[start + 1..i].iter_mut().enumerate() {
+                    stop.1 = Some(start_input + (end_input - start_input) * (j + 1) as f64 / count);
+                }
+                start = i;
             }
         }
-    })
+
+        Some(Easing::LinearStops(stops.into_iter().map(|(output, input)| [input.unwrap(), output]).collect()))
+    }
+
+    /// The function mapping a progress in [0, 1] to the eased progress.
+    pub fn into_fn(self) -> Result<Box<dyn Fn(f64) -> f64>, EasingError> {
+        match self {
+            Easing::Linear => Ok(Box::new(linear_easing)),
+            Easing::CubicBezier(points) => Ok(bezier2(points[0], points[1], points[2], points[3])?),
+            Easing::Steps(steps, position) => Ok(Box::new(move |x| steps_easing(x, steps, position))),
+            Easing::LinearStops(stops) => Ok(Box::new(move |x| linear_stops_easing(x, &stops))),
+            Easing::Function(function) => Ok(Box::new(move |x| {
+                function.call1(&JsValue::NULL, &x.into()).ok().and_then(|value| value.as_f64()).unwrap_or(x)
+            })),
+        }
+    }
 }
 
+fn steps_easing(x: f64, steps: u32, position: StepPosition) -> f64 {
+    let (jumps, first_jump) = match position {
+        StepPosition::Start => (steps, 1.0),
+        StepPosition::End => (steps, 0.0),
+        StepPosition::None => (steps - 1, 0.0),
+        StepPosition::Both => (steps + 1, 1.0),
+    };
+    let step = ((x * steps as f64).floor() + first_jump).clamp(0.0, jumps as f64);
+    step / jumps as f64
+}
+
+fn linear_stops_easing(x: f64, stops: &[[f64; 2]]) -> f64 {
+    //The last stop at or before `x`, so a repeated input jumps to its last output
+    let segment = match stops.iter().rposition(|stop| stop[0] <= x) {
+        None => 0,
+        Some(i) if i == stops.len() - 1 => {
+            if x == stops[i][0] {
+                return stops[i][1];
+            }
+            i - 1
+        },
+        Some(i) => i,
+    };
+    let ([start_x, start_y], [end_x, end_y]) = (stops[segment], stops[segment + 1]);
+    if end_x == start_x {
+        return end_y;
+    }
+    start_y + (x - start_x) / (end_x - start_x) * (end_y - start_y)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f64 = 0.0001;
+
+    const EASE: [f64; 4] = [0.25, 0.1, 0.25, 1.0];
+    const EASE_IN: [f64; 4] = [0.42, 0.0, 1.0, 1.0];
+    const EASE_OUT: [f64; 4] = [0.0, 0.0, 0.58, 1.0];
+    const EASE_IN_OUT: [f64; 4] = [0.42, 0.0, 0.58, 1.0];
+
+    fn curve(points: [f64; 4]) -> Box<dyn Fn(f64) -> f64> {
+        bezier2(points[0], points[1], points[2], points[3]).unwrap()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < EPSILON, "expected {expected}, got {actual}");
+    }
+
+    fn parse(easing: &str) -> Box<dyn Fn(f64) -> f64> {
+        Easing::parse(easing).unwrap().into_fn().unwrap()
+    }
+
+    #[test]
+    fn rejects_x_out_of_range() {
+        assert_eq!(bezier2(-0.1, 0.0, 0.5, 1.0).err(), Some(BezierError::XOutOfRange));
+        assert_eq!(bezier2(0.5, 0.0, 1.1, 1.0).err(), Some(BezierError::XOutOfRange));
+    }
+
+    #[test]
+    fn rejects_non_finite_points() {
+        assert_eq!(bezier2(f64::NAN, 0.0, 0.5, 1.0).err(), Some(BezierError::NotFinite));
+        assert_eq!(bezier2(0.5, f64::INFINITY, 0.5, 1.0).err(), Some(BezierError::NotFinite));
+    }
+
+    #[test]
+    fn accepts_y_out_of_range() {
+        let back = bezier2(0.68, -0.6, 0.32, 1.6).unwrap();
+        assert!(back(0.1) < 0.0);
+        assert!(back(0.9) > 1.0);
+    }
+
+    #[test]
+    fn extremes_are_exact() {
+        for points in [EASE, EASE_IN, EASE_OUT, EASE_IN_OUT] {
+            let easing = curve(points);
+            assert_eq!(easing(0.0), 0.0);
+            assert_eq!(easing(1.0), 1.0);
+        }
+    }
+
+    #[test]
+    fn linear_points_are_identity() {
+        let linear = bezier2(0.3, 0.3, 0.7, 0.7).unwrap();
+        for i in 0..=10 {
+            let x = i as f64 / 10.0;
+            assert_eq!(linear(x), x);
+        }
+    }
+
+    #[test]
+    fn matches_css_ease() {
+        let ease = curve(EASE);
+        assert_close(ease(0.25), 0.4085);
+        assert_close(ease(0.5), 0.8024);
+        assert_close(ease(0.75), 0.9604);
+    }
+
+    #[test]
+    fn ease_in_out_is_symmetric() {
+        let ease_in_out = curve(EASE_IN_OUT);
+        assert_close(ease_in_out(0.5), 0.5);
+        for i in 1..50 {
+            let x = i as f64 / 100.0;
+            assert_close(ease_in_out(x) + ease_in_out(1.0 - x), 1.0);
+        }
+    }
+
+    #[test]
+    fn ease_in_mirrors_ease_out() {
+        let ease_in = curve(EASE_IN);
+        let ease_out = curve(EASE_OUT);
+        for i in 1..100 {
+            let x = i as f64 / 100.0;
+            assert_close(ease_in(x), 1.0 - ease_out(1.0 - x));
+        }
+    }
+
+    #[test]
+    fn lands_on_the_curve() {
+        //Walk the curve by t and check that x(t) maps back to y(t)
+        for points in [EASE, EASE_IN, EASE_OUT, EASE_IN_OUT, [0.25, 0.0, 0.35, 1.0], [0.68, -0.6, 0.32, 1.6]] {
+            let easing = curve(points);
+            for i in 1..100 {
+                let t = i as f64 / 100.0;
+                let x = calc_bezier(t, points[0], points[2]);
+                let y = calc_bezier(t, points[1], points[3]);
+                assert_close(easing(x), y);
+            }
+        }
+    }
+
+    #[test]
+    fn is_monotonic_for_monotonic_curves() {
+        for points in [EASE, EASE_IN, EASE_OUT, EASE_IN_OUT] {
+            let easing = curve(points);
+            let mut last = 0.0;
+            for i in 1..=1000 {
+                let value = easing(i as f64 / 1000.0);
+                assert!(value >= last);
+                last = value;
+            }
+        }
+    }
+
+    #[test]
+    fn parses_presets() {
+        assert_eq!(Easing::parse("linear"), Ok(Easing::Linear));
+        assert_eq!(Easing::parse("ease"), Ok(Easing::CubicBezier(EASE)));
+        assert_eq!(Easing::parse(" ease-in-out "), Ok(Easing::CubicBezier(EASE_IN_OUT)));
+        assert_eq!(Easing::parse("easeOutExpo"), Ok(Easing::CubicBezier([0.16, 1.0, 0.3, 1.0])));
+        assert_eq!(Easing::parse("step-start"), Ok(Easing::Steps(1, StepPosition::Start)));
+        assert_eq!("easeInOutBack".parse::<Easing>(), Ok(Easing::CubicBezier([0.68, -0.6, 0.32, 1.6])));
+    }
+
+    #[test]
+    fn rejects_unknown_easings() {
+        assert_eq!(Easing::parse("bounce"), Err(EasingError::Unknown("bounce".to_string())));
+        assert_eq!(Easing::parse("spring(1, 2)"), Err(EasingError::Unknown("spring(1, 2)".to_string())));
+        assert_eq!(Easing::parse(""), Err(EasingError::Unknown("".to_string())));
+    }
+
+    #[test]
+    fn parses_cubic_bezier() {
+        assert_eq!(Easing::parse("cubic-bezier(.2,.8,.2,1)"), Ok(Easing::CubicBezier([0.2, 0.8, 0.2, 1.0])));
+        assert_eq!(Easing::parse("cubic-bezier( 0.25 , 0.1, 0.25, 1 )"), Ok(Easing::CubicBezier(EASE)));
+        assert_eq!(Easing::parse("cubic-bezier(1.2, 0, 0.5, 1)"), Err(EasingError::Bezier(BezierError::XOutOfRange)));
+        assert!(matches!(Easing::parse("cubic-bezier(0.2, 0.8, 0.2)"), Err(EasingError::InvalidArguments(_))));
+        assert!(matches!(Easing::parse("cubic-bezier(a, 0.8, 0.2, 1)"), Err(EasingError::InvalidArguments(_))));
+    }
+
+    #[test]
+    fn parses_steps() {
+        assert_eq!(Easing::parse("steps(4)"), Ok(Easing::Steps(4, StepPosition::End)));
+        assert_eq!(Easing::parse("steps(4, end)"), Ok(Easing::Steps(4, StepPosition::End)));
+        assert_eq!(Easing::parse("steps(3, jump-both)"), Ok(Easing::Steps(3, StepPosition::Both)));
+        assert!(matches!(Easing::parse("steps(0)"), Err(EasingError::InvalidArguments(_))));
+        assert!(matches!(Easing::parse("steps(1, jump-none)"), Err(EasingError::InvalidArguments(_))));
+        assert!(matches!(Easing::parse("steps(2, middle)"), Err(EasingError::InvalidArguments(_))));
+    }
+
+    #[test]
+    fn evaluates_steps() {
+        let end = parse("steps(4, end)");
+        assert_eq!([end(0.1), end(0.25), end(0.6), end(1.0)], [0.0, 0.25, 0.5, 1.0]);
+
+        let start = parse("steps(4, start)");
+        assert_eq!([start(0.1), start(0.25), start(0.6), start(1.0)], [0.25, 0.5, 0.75, 1.0]);
+
+        let none = parse("steps(5, jump-none)");
+        assert_eq!([none(0.1), none(0.5), none(0.9), none(1.0)], [0.0, 0.5, 1.0, 1.0]);
+
+        let both = parse("steps(3, jump-both)");
+        assert_eq!([both(0.1), both(0.5), both(1.0)], [0.25, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn parses_linear_stops() {
+        assert_eq!(Easing::parse("linear(0, .5 40%, 1)"), Ok(Easing::LinearStops(vec![[0.0, 0.0], [0.4, 0.5], [1.0, 1.0]])));
+        //Missing inputs are spread between their neighbours
+        assert_eq!(Easing::parse("linear(0, 0.25, 0.5, 1)"), Ok(Easing::LinearStops(vec![[0.0, 0.0], [1.0 / 3.0, 0.25], [2.0 / 3.0, 0.5], [1.0, 1.0]])));
+        //Two inputs make a flat segment, and lower inputs are raised
+        assert_eq!(Easing::parse("linear(0, 0.5 20% 60%, 0.8 50%, 1)"), Ok(Easing::LinearStops(vec![[0.0, 0.0], [0.2, 0.5], [0.6, 0.5], [0.6, 0.8], [1.0, 1.0]])));
+        assert!(matches!(Easing::parse("linear(0)"), Err(EasingError::InvalidArguments(_))));
+        assert!(matches!(Easing::parse("linear(0, 0.5 40, 1)"), Err(EasingError::InvalidArguments(_))));
+    }
+
+    #[test]
+    fn evaluates_linear_stops() {
+        let easing = parse("linear(0, .5 40%, 1)");
+        assert_close(easing(0.2), 0.25);
+        assert_close(easing(0.4), 0.5);
+        assert_close(easing(0.7), 0.75);
+        assert_eq!(easing(1.0), 1.0);
+
+        let plateau = parse("linear(0, 0.5 20% 60%, 1)");
+        assert_close(plateau(0.4), 0.5);
+        assert_close(plateau(0.8), 0.75);
+
+        let jump = parse("linear(0, 0.2 50%, 0.8 50%, 1)");
+        assert_close(jump(0.25), 0.1);
+        assert_close(jump(0.5), 0.8);
+        assert_close(jump(0.75), 0.9);
+    }
+
+    #[test]
+    fn parsed_curves_match_bezier2() {
+        let parsed = parse("cubic-bezier(0.42, 0, 0.58, 1)");
+        let ease_in_out = curve(EASE_IN_OUT);
+        for i in 0..=20 {
+            let x = i as f64 / 20.0;
+            assert_eq!(parsed(x), ease_in_out(x));
+        }
+    }
+}
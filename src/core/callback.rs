@@ -18,14 +18,9 @@ impl Core {
         let callback: Rc<RefCell<Option<Closure<dyn FnMut() >>>> = Rc::new(RefCell::new(None));
         *callback.borrow_mut() = Some(Closure::new(move || {
             let core = core.clone();
-            let core_ref = core.borrow();
-            let option = core_ref.scroll.get_option();
-            let element = option.el.clone();
-            let namespace = &core.as_ref().borrow().namespace;
+            let option = core.borrow().scroll.get_option();
 
-
-            let scroll_event = Event::new(&format!("{}scroll", namespace.as_ref().borrow())).unwrap();
-            element.dispatch_event(&scroll_event);
+            Core::dispatch_scroll(core, &option);
         }));
         {
             core2.borrow_mut().check_scroll = callback;
@@ -37,6 +32,7 @@ impl Core {
         let callback: Rc<RefCell<Option<Closure<dyn FnMut() >>>> = Rc::new(RefCell::new(None));
         let core2 = core.clone();
         let cb = core.borrow().check_resize_cb_2.clone();
+        let resize_raf = core.borrow().resize_raf.clone();
 
         
         *callback.borrow_mut() = Some(Closure::new(move || {
@@ -49,7 +45,7 @@ impl Core {
                 {
                     *core.borrow().resize_tick.as_ref().borrow_mut() = true;
                 }
-                window().unwrap().request_animation_frame(cb.borrow().as_ref().unwrap().as_ref().unchecked_ref()).unwrap();
+                *resize_raf.borrow_mut() = Some(window().unwrap().request_animation_frame(cb.borrow().as_ref().unwrap().as_ref().unchecked_ref()).unwrap());
             }   
         }));
         {
@@ -71,21 +67,36 @@ impl Core {
         }
     }
 
-    pub fn check_event_callback(core: Rc<RefCell<Core>>) {
-        let callback: Rc<RefCell<Option<Closure<dyn FnMut(Event) >>>> = Rc::new(RefCell::new(None));
+    pub fn check_hash_callback(core: Rc<RefCell<Core>>) {
+        let callback: Rc<RefCell<Option<Closure<dyn FnMut() >>>> = Rc::new(RefCell::new(None));
         let core2 = core.clone();
 
-        *callback.borrow_mut() = Some(Closure::new(move |event: Event| {
-            let core = core.clone();
-            let namespace = &core.as_ref().borrow().namespace;
-            let _event_name = event.type_().replace(&namespace.as_ref().borrow().to_string(), "");
-            //todo
+        //`hashchange` and `popstate` both fire on anchor navigation, the second one is a no-op
+        *callback.borrow_mut() = Some(Closure::new(move || {
+            Core::scroll_to_hash(core.clone(), None);
         }));
 
         {
-            core2.as_ref().borrow_mut().check_event = callback;
+            core2.as_ref().borrow_mut().check_hash = callback;
+        }
+    }
+
+    pub fn hash_load_callback(core: Rc<RefCell<Core>>) {
+        let callback: Rc<RefCell<Option<Closure<dyn FnMut() >>>> = Rc::new(RefCell::new(None));
+        let core2 = core.clone();
+        let hash_load_raf = core.borrow().hash_load_raf.clone();
+
+        //jump straight to the target, there is nothing to animate from on load
+        *callback.borrow_mut() = Some(Closure::new(move || {
+            {
+                *hash_load_raf.borrow_mut() = None;
+            }
+            Core::scroll_to_hash(core.clone(), Some(0.0));
+        }));
+
+        {
+            core2.as_ref().borrow_mut().hash_load_cb = callback;
         }
-        
     }
 
     pub fn set_scroll_to_callback(core: Rc<RefCell<Core>>, options: &LocomotiveOption) {
@@ -93,13 +104,12 @@ impl Core {
         let name = options.name.clone();
         let core = core.clone();
         let core2 = core.clone();
-        let options = options.clone();
 
 
         *callback.borrow_mut() = Some(Closure::new(move |event: Event| {
             let core = core.clone();
             let scroll = core.as_ref().borrow().scroll.clone();
-            let options = options.clone();
+            let options = scroll.get_option();
 
             event.prevent_default();
 
@@ -116,7 +126,7 @@ impl Core {
                 ..Default::default()
             };
 
-            Core::scroll_to(scroll.clone(), target, option, core.clone(), &options);
+            let _ = Core::scroll_to(scroll.clone(), target, option, core.clone(), &options);
         }));
 
         {
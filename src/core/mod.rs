@@ -1,16 +1,23 @@
 mod callback;
 mod utils;
+mod registry;
 
-use std::{rc::Rc, cell::RefCell};
+use std::{rc::{Rc, Weak}, cell::RefCell, collections::HashMap};
 
-use wasm_bindgen::{JsCast, prelude::Closure};
-use web_sys::{Element, NodeList, window, Event};
+use convert_js::{ToJs, __internal::JsObject};
+use js_sys::{Promise, Array, Function};
+use wasm_bindgen::{JsCast, JsValue, prelude::Closure};
+use web_sys::{Element, window, console, Event, CustomEvent, CustomEventInit, AddEventListenerOptions};
 
-use crate::{option::{LocomotiveOption, Position}, Scroll, smooth::SmoothScroll, utils::{current_elements::CurrentElements, listeners::Listeners, els::{Els, MappedEl, ScrollToTarget, ScrollToOption}, instance::Instance, element_type::ElementType}};
+use crate::{error::LocomotiveError, option::{LocomotiveOption, Position, UpdateOption, Context, ContextOption, OptionValue}, engine::{self, Viewport, ViewChange}, Scroll, smooth::SmoothScroll, native::NativeScroll, utils::{current_elements::CurrentElements, listeners::{Listeners, ListenerEvent, Pending, Queue}, els::{Els, MappedEl, ScrollToTarget, ScrollToOption}, instance::Instance}};
 
 
 
 
+thread_local! {
+    static INSTANCES: RefCell<registry::Registry<Element, Weak<RefCell<Core>>>> = const { RefCell::new(registry::Registry::new()) };
+}
+
 
 #[derive(Clone, Debug)]
 pub struct Core {
@@ -21,20 +28,29 @@ pub struct Core {
     pub window_middle: Position,
     pub els: Rc<RefCell<Els>>,
     pub current_elements: Rc<RefCell<CurrentElements>>,
-    pub listeners: Listeners,
+    pub listeners: Rc<RefCell<Listeners>>,
+    /// Events waiting for the core to be released, see `Core::flush`.
+    pub pending: Rc<RefCell<Queue<Pending>>>,
     pub has_scroll_ticking: Rc<RefCell<bool>>,
     pub has_scroll_bar: Rc<RefCell<Option<bool>>>,
     pub scroll_bar_limit: Rc<RefCell<Position>>,
+    /// Whether an `on("call")` handler got registered, the `{namespace}call` DOM event is dispatched either way.
     pub has_call_event_set: bool,
+    /// Set while the scroll is rebuilt, the elements in view already got their calls.
+    pub mute_calls: bool,
     pub check_scroll: Rc<RefCell<Option<Closure<dyn FnMut()>>>>,
     pub check_resize: Rc<RefCell<Option<Closure<dyn FnMut()>>>>,
-    pub check_event: Rc<RefCell<Option<Closure<dyn FnMut(Event)>>>>,
     pub set_scroll_to: Rc<RefCell<Option<Closure<dyn FnMut(Event)>>>>,
+    pub check_hash: Rc<RefCell<Option<Closure<dyn FnMut()>>>>,
+    pub hash_load_cb: Rc<RefCell<Option<Closure<dyn FnMut()>>>>,
+    pub hash_load_raf: Rc<RefCell<Option<i32>>>,
+    pub hash: Rc<RefCell<String>>,
     pub instance: Rc<RefCell<Instance>>,
-    pub context: Rc<RefCell<String>>,
+    pub context: Rc<RefCell<Context>>,
     pub direction_axis: Rc<RefCell<char>>,
     pub resize_tick: Rc<RefCell<bool>>,
-    pub scroll_to_els: Option<NodeList>,
+    pub resize_raf: Rc<RefCell<Option<i32>>>,
+    pub scroll_to_els: Vec<Element>,
     pub scroll: Scroll,
 
     //Scroll
@@ -48,10 +64,11 @@ pub struct Core {
 
 
 impl Core {
-    pub fn new(mut options: LocomotiveOption) -> Rc<RefCell<Self>> {
+    pub fn new(mut options: LocomotiveOption) -> Result<Rc<RefCell<Self>>, LocomotiveError> {
         let window = window().unwrap();
 
-        if Self::check_smooth_scroll(&options) {
+        let smooth = Self::check_smooth_scroll(&options)?;
+        if smooth {
             if window.history().unwrap().scroll_restoration().is_err() {
                 window.history().unwrap().set_scroll_restoration(web_sys::ScrollRestoration::Manual).unwrap()
             }
@@ -65,26 +82,13 @@ impl Core {
             y: window_height / 2.,
             x: window_width / 2.
         };
-        let current_elements = CurrentElements::new();
+        let current_elements = Rc::new(RefCell::new(CurrentElements::new()));
         let mut instance = Instance::new(&html, current_elements.clone());
 
-        let context = match (options.is_mobile, options.is_tablet) {
-            (true, true) => {
-                options.direction = options.tablet.as_ref().unwrap().direction.clone();
-                "tablet".to_string()
-            },
-            (true, false) => {
-                options.direction = options.smartphone.as_ref().unwrap().direction.clone();
-                "smartphone".to_string()
-            },
-            (false, _) => "desktop".to_string(),
-        };
+        let context = options.current_context();
+        options.direction = options.context_option(context)?.direction;
         
-        let direction_axis = if options.direction.as_str() == "horizontal" {
-            'x'
-        } else {
-            'y'
-        };
+        let direction_axis = options.direction.axis();
 
         if options.get_direction {
             instance.direction = None;
@@ -92,6 +96,7 @@ impl Core {
         }
 
         html.class_list().add_1(&options.init_class).unwrap();
+        options.el.get_element().set_attribute(&options.names.as_ref().unwrap().instance, "").unwrap();
         
 
         let html = Rc::new(RefCell::new(html));
@@ -99,27 +104,33 @@ impl Core {
         let direction_axis = Rc::new(RefCell::new(direction_axis));
 
         let core = Core {
-            namespace: Rc::new(RefCell::new("locomotive".to_string())),
+            namespace: Rc::new(RefCell::new(options.namespace.clone())),
             html: html.clone(),
             window_height, 
             window_width,
             window_middle,
             els: Rc::new(RefCell::new(Els::new())),
-            current_elements: Rc::new(RefCell::new(current_elements)), 
-            listeners: Listeners::new(),
+            current_elements, 
+            listeners: Rc::new(RefCell::new(Listeners::new())),
+            pending: Rc::new(RefCell::new(Queue::new())),
             has_scroll_ticking: Rc::new(RefCell::new(false)),
             has_scroll_bar: Rc::new(RefCell::new(None)),
             scroll_bar_limit: Rc::new(RefCell::new(Position::new(0.0, 0.0))),
             has_call_event_set: false, 
+            mute_calls: false,
             check_scroll: Rc::new(RefCell::new(None)),
             check_resize: Rc::new(RefCell::new(None)),
-            check_event: Rc::new(RefCell::new(None)), 
             set_scroll_to: Rc::new(RefCell::new(None)),
+            check_hash: Rc::new(RefCell::new(None)),
+            hash_load_cb: Rc::new(RefCell::new(None)),
+            hash_load_raf: Rc::new(RefCell::new(None)),
+            hash: Rc::new(RefCell::new(String::new())),
             instance: instance.clone(),
             context: Rc::new(RefCell::new(context)),
             direction_axis: direction_axis.clone(),
             resize_tick: Rc::new(RefCell::new(false)),
-            scroll_to_els: None,
+            resize_raf: Rc::new(RefCell::new(None)),
+            scroll_to_els: vec![],
             scroll: Scroll::None,
 
             call_way: Rc::new(RefCell::new(String::new())),
@@ -131,7 +142,7 @@ impl Core {
 
         let core = Rc::new(RefCell::new(core));
 
-        let scroll = Core::create_scroll(options.clone(),  core.clone());
+        let scroll = Core::create_scroll(options.clone(), smooth, core.clone());
         {
             core.borrow_mut().scroll = scroll;
         }
@@ -141,132 +152,373 @@ impl Core {
         Core::check_resize_cb_2(core.clone());
         Core::check_resize_callback(core.clone());
         Core::set_scroll_to_callback(core.clone(), &options);
+        Core::check_hash_callback(core.clone());
+        Core::hash_load_callback(core.clone());
       
-        core
+        Ok(core)
 
     }
 
-    pub fn init(core: Rc<RefCell<Core>>) {
+    pub fn init(core: Rc<RefCell<Core>>) -> Result<(), LocomotiveError> {
         //self.scroll.as_mut().unwrap().init(self.html.clone(), instance);
         let mut _smooth_scroll = false;
         let mut _options = None;
 
         {
             _smooth_scroll = core.borrow().scroll.is_smooth();
-            _options = Some(core.borrow().scroll.get_option().clone());
+            _options = Some(core.borrow().scroll.get_option());
         }
 
         match _smooth_scroll {
             true => {
-                SmoothScroll::init(core.clone(), _options.as_ref().unwrap());
+                SmoothScroll::init(core.clone(), _options.as_ref().unwrap())?;
             },
             _ => {
-                todo!()
+                NativeScroll::init(core.clone(), _options.as_ref().unwrap())?;
             }
         }
 
-        Core::init_events(core, _options.as_ref().unwrap());
+        Core::init_events(core.clone(), _options.as_ref().unwrap());
+        Core::flush(&core);
+        Ok(())
     }
 
     pub fn init_events(core: Rc<RefCell<Core>>, options: &LocomotiveOption) {
 
         {
-            core.borrow_mut().scroll_to_els = options.el.query_selector_all(&format!("[data-{}-to", options.name));
+            core.borrow_mut().scroll_to_els = options.query_own(&format!("[data-{}-to]", options.name));
         }
 
         let core = core.clone();
         let core_ref = core.as_ref().borrow();
 
-        if let Some(node_list) = core_ref.scroll_to_els.as_ref() {
-            for i in 0 .. node_list.length() {
-                let node = node_list.get(i).unwrap();
-                let cb = core.as_ref().borrow();
-                let cb = cb.set_scroll_to.borrow();
-                let cb = cb.as_ref().unwrap();
+        if let Some(cb) = core_ref.set_scroll_to.borrow().as_ref() {
+            for node in core_ref.scroll_to_els.iter() {
                 node.add_event_listener_with_callback_and_bool("click", cb.as_ref().unchecked_ref(), false).unwrap();
             }
         }
+
+        let window = window().unwrap();
+        if let Some(cb) = core_ref.check_hash.borrow().as_ref() {
+            window.add_event_listener_with_callback("hashchange", cb.as_ref().unchecked_ref()).unwrap();
+            window.add_event_listener_with_callback("popstate", cb.as_ref().unchecked_ref()).unwrap();
+        }
+
+        //deep link, the target is only looked up once the page is laid out
+        if !window.location().hash().unwrap_or_default().is_empty() {
+            if let Some(cb) = core_ref.hash_load_cb.borrow().as_ref() {
+                if window.document().unwrap().ready_state() == "complete" {
+                    *core_ref.hash_load_raf.borrow_mut() = window.request_animation_frame(cb.as_ref().unchecked_ref()).ok();
+                } else {
+                    let listener_options = AddEventListenerOptions::new();
+                    listener_options.set_once(true);
+                    window.add_event_listener_with_callback_and_add_event_listener_options("load", cb.as_ref().unchecked_ref(), &listener_options).unwrap();
+                }
+            }
+        }
     }
 }
 
 impl Core {
 
-    fn create_scroll(options: LocomotiveOption, core: Rc<RefCell<Core>>) -> Scroll {
+    fn create_scroll(options: LocomotiveOption, smooth: bool, core: Rc<RefCell<Core>>) -> Scroll {
     //Setup the `Scroll` Field
+        let options = Rc::new(RefCell::new(options));
 
-        if (options.smooth && !options.is_mobile)  || 
-            (options.tablet.as_ref().unwrap().smooth && options.is_tablet) ||
-            (options.smartphone.as_ref().unwrap().smooth && options.is_mobile && !options.is_tablet) 
-            {
+        if smooth {
                 let scroll = SmoothScroll::new(options, core);
                 Scroll::Smooth(scroll)
         } else {
-                //let scroll = NativeScroll::new(options, window, check_scroll_cb);
-                //Scroll::Native(scroll)
-                todo!()
+                let scroll = NativeScroll::new(options, core);
+                Scroll::Native(scroll)
         }
     }
 
-    pub(crate) fn check_context(core: &mut Core) {
-        let is_smooth = core.scroll.is_smooth();
-        let options = core.scroll.get_mut_option();
+    /// The previous context if it changed since the last check.
+    pub(crate) fn check_context(core: &Core) -> Option<Context> {
+        let mut options = core.scroll.get_mut_option();
         if !options.reload_on_context_change {
-            return;
+            return None;
         }
 
+        let new_context = match options.detect_context() {
+            Ok(context) => context,
+            Err(err) => {
+                console::error_1(&err.to_js());
+                return None;
+            }
+        };
+        let old_context = core.context.replace(new_context);
 
-        let is_mobile = options.check_mobile_bool();
-        {   
-            match is_smooth {
-                true => options.is_mobile = is_mobile,
-                false => todo!()
+        (old_context != new_context).then_some(old_context)
+    }
+
+    /// Rebuild the scroll if the new context has another `smooth` or `direction`, then emit `contextChange`.
+    fn change_context(core: Rc<RefCell<Core>>, previous: Context) {
+        let options = core.borrow().scroll.get_option();
+        let context = *core.borrow().context.borrow();
+
+        let context_option = match (options.context_option(previous), options.context_option(context)) {
+            (Ok(previous_option), Ok(context_option)) => {
+                if previous_option.smooth != context_option.smooth || previous_option.direction != context_option.direction {
+                    if let Err(err) = Core::switch_scroll(core.clone(), &context_option) {
+                        console::error_1(&err.to_js());
+                    }
+                }
+                context_option
+            },
+            (Err(err), _) | (_, Err(err)) => {
+                console::error_1(&err.to_js());
+                return;
             }
+        };
+
+        let detail = JsObject::new();
+        detail.set_prop(&"context", &context.as_str());
+        detail.set_prop(&"previous", &previous.as_str());
+        detail.set_prop(&"smooth", &context_option.smooth);
+        let detail = detail.into_js_value();
+
+        let event_init = CustomEventInit::new();
+        event_init.set_detail(&detail);
+        let event = CustomEvent::new_with_event_init_dict(&format!("{}contextchange", core.borrow().namespace.borrow()), &event_init).unwrap();
+        options.el.dispatch_event(&event);
+
+        let listeners = core.borrow().listeners.clone();
+        Listeners::emit(&listeners, ListenerEvent::ContextChange, &Array::of1(&detail));
+    }
+
+    /// Replace the `Scroll` in place with one built for `context_option`. The scroll position,
+    /// the stop state and which elements are in view carry over, the listeners stay on the core.
+    fn switch_scroll(core: Rc<RefCell<Core>>, context_option: &ContextOption) -> Result<(), LocomotiveError> {
+        let (mut options, scroll, stopped, previous) = {
+            let core_ref = core.borrow();
+            let scroll = core_ref.instance.borrow().scroll.clone();
+            let previous = core_ref.els.borrow().data.iter()
+                .map(|(id, el)| {
+                    let el = el.borrow();
+                    (id.clone(), (el.in_view == Some(true), el.call.is_some()))
+                })
+                .collect::<HashMap<_, _>>();
+            (core_ref.scroll.get_option(), scroll, core_ref.scroll.is_stopped(), previous)
+        };
+
+        Core::destroy_scroll(core.clone(), &options);
+        {
+            let core_ref = core.borrow();
+            core_ref.current_elements.borrow_mut().data.clear();
+            *core_ref.direction_axis.borrow_mut() = context_option.direction.axis();
+        }
+
+        //the smooth scroll starts from the top and moves its content to `init_position`
+        options.direction = context_option.direction;
+        options.init_position = scroll.clone();
+        if context_option.smooth {
+            window().unwrap().scroll_to_with_x_and_y(0., 0.);
         }
-        let is_tablet = options.check_tablet_bool();
+
+        let new_scroll = Core::create_scroll(options.clone(), context_option.smooth, core.clone());
+        //after `reset_native_scroll`, the native scroll picks up where the smooth one was
+        if let Scroll::Native(native) = &new_scroll {
+            native.scroller.scroll_to(scroll.x, scroll.y);
+        }
+        //elements in view already got their calls, they are matched against `previous` below
         {
-            match is_smooth {
-                true => options.is_tablet = is_tablet,
-                false => todo!()
-            }
+            let mut core_mut = core.borrow_mut();
+            core_mut.scroll = new_scroll;
+            core_mut.mute_calls = true;
         }
-         
-        let old_context = core.context.as_ref().clone().into_inner();
-        match (options.is_mobile, options.is_tablet) {
-            (true, true) => *core.context.borrow_mut() = "tablet".to_string(),
-            (true, false) => *core.context.borrow_mut() = "smartphone".to_string(),
-            _ => *core.context.borrow_mut() = "desktop".to_string(),
+        let result = if context_option.smooth {
+            SmoothScroll::init(core.clone(), &options)
+        } else {
+            NativeScroll::init(core.clone(), &options)
+        };
+        core.borrow_mut().mute_calls = false;
+        result?;
+
+        Core::restore_view(core.clone(), &previous, &options);
+        if stopped {
+            Core::stop_scroll(core);
         }
 
-        if old_context.as_str() != core.context.borrow().as_str() {
-            let old_smooth = match old_context.as_str() {
-                "desktop" => options.smooth,
-                "tablet" => options.tablet.as_ref().unwrap().smooth,
-                "smartphone" => options.tablet.as_ref().unwrap().smooth,
-                _ => panic!("device not supported") 
-            };
-            let new_smooth = match core.context.borrow().as_str() {
-                "desktop" => options.smooth,
-                "tablet" => options.tablet.as_ref().unwrap().smooth,
-                "smartphone" => options.smartphone.as_ref().unwrap().smooth,
-                _ => panic!("device not supported")
-            };
+        Ok(())
+    }
+
+    /// Only the elements that entered or left the view with the new layout fire their call, the others keep their state.
+    fn restore_view(core: Rc<RefCell<Core>>, previous: &HashMap<String, (bool, bool)>, options: &LocomotiveOption) {
+        let els = core.borrow().els.clone();
 
-            if old_smooth != new_smooth {
-                window().unwrap().location().reload().unwrap();
+        for (id, mapped_el) in els.borrow().data.iter() {
+            let mut el = mapped_el.borrow_mut();
+            let (was_in_view, has_call) = previous.get(id).copied().unwrap_or((false, true));
+            //a call that does not repeat only fires once
+            if !has_call {
+                el.call = None;
+            }
+            if el.call.is_none() {
+                continue;
+            }
+
+            match (was_in_view, el.in_view == Some(true)) {
+                (false, true) => {
+                    Core::dispatch_call(&mut el, "enter", options, core.clone(), true);
+                    if !*el.repeat.as_ref().unwrap() {
+                        el.call = None;
+                    }
+                },
+                (true, false) => Core::dispatch_call(&mut el, "exit", options, core.clone(), true),
+                _ => {}
             }
         }
-        
+
+        Core::flush(&core);
     }
 
 }
 
 //INIT FUNCTIONS
 impl Core {
-    pub fn scroll_to(scroll: Scroll, target: ScrollToTarget, scroll_to_option: ScrollToOption, core: Rc<RefCell<Core>>, options: &LocomotiveOption) {
+    pub fn stop_scroll(core: Rc<RefCell<Core>>) {
+        let (is_smooth, options) = {
+            let core_ref = core.borrow();
+            (core_ref.scroll.is_smooth(), core_ref.scroll.get_option())
+        };
+
+        if is_smooth {
+            SmoothScroll::stop_scroll(core.clone(), &options);
+        } else {
+            NativeScroll::stop_scroll(core.clone());
+        }
+
+        let _ = core.borrow().html.borrow().class_list().add_1(&options.stop_class);
+    }
+
+    /// Re-measure the page, e.g. after lazy content changed the layout.
+    pub fn update(core: Rc<RefCell<Core>>, update_option: &UpdateOption) -> Result<(), LocomotiveError> {
+        let (is_smooth, options) = {
+            let core_ref = core.borrow();
+            (core_ref.scroll.is_smooth(), core_ref.scroll.get_option())
+        };
+
+        let result = if is_smooth {
+            SmoothScroll::update(core.clone(), &options, update_option)
+        } else {
+            NativeScroll::update(core.clone(), &options)
+        };
+        Core::flush(&core);
+        result
+    }
+
+    /// Once built, the enclosing instances let go of the elements in its container.
+    pub fn register(core: &Rc<RefCell<Core>>) {
+        let options = core.borrow().scroll.get_option();
+        let container = options.el.get_element().clone();
+        let enclosing = INSTANCES.with(|instances| {
+            instances.borrow_mut().add(&options.name, container, Rc::downgrade(core), |outer, inner| outer.contains(Some(inner)))
+        });
+        Core::remeasure(enclosing);
+    }
+
+    fn remeasure(cores: Vec<Weak<RefCell<Core>>>) {
+        for core in cores.iter().filter_map(Weak::upgrade) {
+            if let Err(err) = Core::update(core, &UpdateOption { preserve_scroll: true }) {
+                console::error_1(&err.to_js());
+            }
+        }
+    }
+
+    /// Tear the instance down. Once the closures are dropped nothing references the core anymore,
+    /// so it is freed with the last `Rc`.
+    pub fn destroy(core: Rc<RefCell<Core>>) {
+        let options = core.borrow().scroll.get_option();
+        Core::destroy_scroll(core.clone(), &options);
+
+        let core_ref = core.borrow();
+        let window = window().unwrap();
+
+        if let Some(cb) = core_ref.check_resize.borrow().as_ref() {
+            let _ = window.remove_event_listener_with_callback("resize", cb.as_ref().unchecked_ref());
+        }
+        if let Some(handler) = core_ref.resize_raf.borrow_mut().take() {
+            let _ = window.cancel_animation_frame(handler);
+        }
+        if let Some(cb) = core_ref.check_hash.borrow().as_ref() {
+            let _ = window.remove_event_listener_with_callback("hashchange", cb.as_ref().unchecked_ref());
+            let _ = window.remove_event_listener_with_callback("popstate", cb.as_ref().unchecked_ref());
+        }
+        if let Some(cb) = core_ref.hash_load_cb.borrow().as_ref() {
+            let _ = window.remove_event_listener_with_callback("load", cb.as_ref().unchecked_ref());
+        }
+        if let Some(handler) = core_ref.hash_load_raf.borrow_mut().take() {
+            let _ = window.cancel_animation_frame(handler);
+        }
+        if let Some(cb) = core_ref.set_scroll_to.borrow().as_ref() {
+            for node in core_ref.scroll_to_els.iter() {
+                let _ = node.remove_event_listener_with_callback("click", cb.as_ref().unchecked_ref());
+            }
+        }
+
+        {
+            let html = core_ref.html.borrow();
+            let class_list = html.class_list();
+            for class in [&options.init_class, &options.stop_class] {
+                let _ = class_list.remove_1(class);
+            }
+            let _ = options.el.get_element().remove_attribute(&options.names.as_ref().unwrap().instance);
+        }
+
+        core_ref.check_scroll.borrow_mut().take();
+        core_ref.check_resize.borrow_mut().take();
+        core_ref.check_resize_cb_2.borrow_mut().take();
+        core_ref.set_scroll_to.borrow_mut().take();
+        core_ref.check_hash.borrow_mut().take();
+        core_ref.hash_load_cb.borrow_mut().take();
+
+        //the enclosing instances take the elements back
+        let enclosing = INSTANCES.with(|instances| {
+            instances.borrow_mut().remove(&options.name, options.el.get_element(), |outer, inner| outer.contains(Some(inner)))
+        });
+        drop(core_ref);
+        Core::remeasure(enclosing);
+    }
+
+    /// Tear down what the smooth or native scroll added, the core keeps its own listeners.
+    fn destroy_scroll(core: Rc<RefCell<Core>>, options: &LocomotiveOption) {
+        if core.borrow().scroll.is_smooth() {
+            SmoothScroll::destroy(core.clone(), options);
+        } else {
+            NativeScroll::destroy(core.clone());
+        }
+
+        let core_ref = core.borrow();
+        let html = core_ref.html.borrow();
+        let class_list = html.class_list();
+        for class in [&options.smooth_class, &options.scrolling_class, &options.dragging_class] {
+            let _ = class_list.remove_1(class);
+        }
+        let _ = html.remove_attribute(&options.names.as_ref().unwrap().data_direction);
+    }
+
+    pub fn start_scroll(core: Rc<RefCell<Core>>) {
+        let (is_smooth, options) = {
+            let core_ref = core.borrow();
+            (core_ref.scroll.is_smooth(), core_ref.scroll.get_option())
+        };
+
+        if is_smooth {
+            SmoothScroll::start_scroll(core.clone());
+        } else {
+            NativeScroll::start_scroll(core.clone());
+        }
+
+        let _ = core.borrow().html.borrow().class_list().remove_1(&options.stop_class);
+    }
+
+    pub fn scroll_to(scroll: Scroll, target: ScrollToTarget, scroll_to_option: ScrollToOption, core: Rc<RefCell<Core>>, options: &LocomotiveOption) -> Promise {
         //self.scroll.as_ref().unwrap().scroll_to(target_el, attr, None);
         match scroll {
-            Scroll::_Native(_) => {
-                //NativeScroll::scroll_to(target, scroll_to_option, html, instance);
+            Scroll::Native(_) => {
+                NativeScroll::scroll_to(target, scroll_to_option, core.clone())
             },
             Scroll::Smooth(_) => {
                 SmoothScroll::scroll_to(target, scroll_to_option, core.clone(), options)
@@ -274,6 +526,56 @@ impl Core {
             _ => todo!()
         }
     }
+
+    /// Scroll to the element the URL hash points to, offset by its `data-{name}-offset`.
+    /// An empty hash goes back to the top. Nothing happens if the hash did not change since the last call.
+    pub fn scroll_to_hash(core: Rc<RefCell<Core>>, duration: Option<f64>) {
+        let window = window().unwrap();
+        let hash = window.location().hash().unwrap_or_default();
+        let id = match js_sys::decode_uri_component(hash.trim_start_matches('#')) {
+            Ok(id) => String::from(id),
+            Err(_) => return,
+        };
+
+        let (scroll, options, window_size) = {
+            let core_ref = core.borrow();
+            if *core_ref.hash.borrow() == id {
+                return;
+            }
+            *core_ref.hash.borrow_mut() = id.clone();
+
+            let window_size = match *core_ref.direction_axis.borrow() {
+                'x' => core_ref.window_width,
+                _ => core_ref.window_height,
+            };
+            (core_ref.scroll.clone(), core_ref.scroll.get_option(), window_size)
+        };
+
+        let (target, offset) = if id.is_empty() {
+            (ScrollToTarget::String("top".to_string()), None)
+        } else {
+            match window.document().unwrap().get_element_by_id(&id) {
+                Some(el) => {
+                    let offset = el.get_attribute(&format!("data-{}-offset", options.name)).map(|offset| Core::hash_offset(&offset, window_size));
+                    (ScrollToTarget::Element(el), offset)
+                },
+                None => return,
+            }
+        };
+        let option = ScrollToOption {
+            offset,
+            duration,
+            ..Default::default()
+        };
+
+        let _ = Core::scroll_to(scroll, target, option, core, &options);
+    }
+
+    /// First value of a `data-{name}-offset` in px, percentages are relative to `window_size`.
+    fn hash_offset(offset: &str, window_size: f64) -> String {
+        let offset = offset.split(',').next().unwrap_or_default().to_string();
+        engine::relative_offset(&[offset], window_size)[0].to_string()
+    }
 }
 
 
@@ -283,71 +585,68 @@ impl Core {
         {
             let core = cores.clone();
             let core = core.as_ref().borrow();
-            let scroll_top = core.instance.as_ref().borrow().scroll.y;
-            let scroll_bottom = scroll_top + core.window_height;
-
-            let scroll_left = core.instance.as_ref().borrow().scroll.x;
-            let scroll_right = scroll_left + core.window_width;
-
+            let viewport = core.viewport();
 
             for (id, mapped_el) in core.els.borrow().data.iter() {
                 let mut el = mapped_el.borrow_mut();
-                if !el.in_view.as_ref().unwrap() || has_call_event_set == Some(true) {
-
-                    if options.direction.as_str() == "horizontal" {
-                        if scroll_right >= el.left && scroll_left < el.right {
-                            Core::set_in_view(&mut el, &id, cores.clone(), options)
-                        }
-                    } else {
-                        if scroll_bottom >= el.top && scroll_top < el.bottom {
-                            Core::set_in_view(&mut el, &id, cores.clone(), options)
-                        }
-                    }
+                let detection = engine::detect(&el.rect(), *el.in_view.as_ref().unwrap(), has_call_event_set == Some(true), &viewport);
+
+                if detection.change == Some(ViewChange::Enter) {
+                    Core::set_in_view(&mut el, id, cores.clone(), options, has_call_event_set == Some(true));
                 }
 
-                if *el.in_view.as_ref().unwrap() {
-                    if options.direction.as_str() == "horizontal" {
-                        let width = el.right - el.left;
-                        let scroll_x = core.instance.as_ref().borrow().scroll.x;
-                        let new_progress = (scroll_x - (el.left - core.window_width)) / (width + core.window_width);
-                        {
-                            el.progress = Some(new_progress);
-                        }
-
-                        if scroll_right < el.left || scroll_left > el.right {
-                            Core::set_out_of_view(&mut el, &id, cores.clone(), options);
-                        }
-                    } else {
-                        let height = el.bottom - el.top;
-                        let scroll_y = core.instance.as_ref().borrow().scroll.y;
-                        let new_progress = (scroll_y - (el.top - core.window_height)) / (height + core.window_height);
-                        {
-                            el.progress = Some(new_progress);
-                        }
-
-                        if scroll_bottom < el.top || scroll_top > el.bottom {
-                            Core::set_out_of_view(&mut el, &id, cores.clone(), options);
-                        }
+                if let Some(progress) = detection.progress {
+                    el.progress = Some(progress);
+                    if let Some(current) = core.current_elements.borrow_mut().data.get_mut(id) {
+                        current.progress = Some(progress);
                     }
                 }
+
+                if detection.change == Some(ViewChange::Exit) {
+                    Core::set_out_of_view(&mut el, id, cores.clone(), options);
+                }
             }
         }
     
 
         *cores.as_ref().borrow().has_scroll_ticking.borrow_mut() = false;
 
+        //the calls are only handed out once the elements and the core are released
+        Core::flush(&cores);
+    }
+
+    /// Scroll position, window size and limit, for the `engine`.
+    pub fn viewport(&self) -> Viewport {
+        let instance = self.instance.borrow();
+        Viewport {
+            scroll: instance.scroll.clone(),
+            size: Position::new(self.window_width, self.window_height),
+            limit: instance.limit.clone(),
+            axis: *self.direction_axis.borrow(),
+        }
     }
 
     fn resize(core: Rc<RefCell<Core>>) {
-        let mut core_mut = core.borrow_mut();
-        let is_smooth = core_mut.scroll.is_smooth();
-        match is_smooth {
-            true => SmoothScroll::resize(&mut core_mut),
-            false => todo!()
+        {
+            let mut core_mut = core.borrow_mut();
+            let is_smooth = core_mut.scroll.is_smooth();
+            match is_smooth {
+                true => SmoothScroll::resize(&mut core_mut),
+                false => NativeScroll::resize(&mut core_mut)
+            }
+        }
+
+        let previous = Core::check_context(&core.borrow());
+        if let Some(previous) = previous {
+            Core::change_context(core.clone(), previous);
         }
+
+        let listeners = core.borrow().listeners.clone();
+        Listeners::emit(&listeners, ListenerEvent::Resize, &Array::new());
     }
 
-    pub fn set_in_view(current: &mut MappedEl, id: &str, core: Rc<RefCell<Core>>, option: &LocomotiveOption) {
+    /// With `recall` the element was already in view, its call only goes to the `on("call")` handlers.
+    pub fn set_in_view(current: &mut MappedEl, id: &str, core: Rc<RefCell<Core>>, option: &LocomotiveOption, recall: bool) {
         
         {
             current.in_view = Some(true);
@@ -362,8 +661,8 @@ impl Core {
         }
 
 
-        if current.call.is_some() && core.as_ref().borrow().has_call_event_set {
-            Core::dispatch_call(current, "enter", option, core.clone());
+        if current.call.is_some() && !core.borrow().mute_calls {
+            Core::dispatch_call(current, "enter", option, core.clone(), !recall);
 
             if !*current.repeat.as_ref().unwrap() {
                 current.call = None;
@@ -376,8 +675,8 @@ impl Core {
         
         core.as_ref().borrow().current_elements.borrow_mut().data.remove(id).unwrap();
 
-        if current.call.is_some() && core.borrow().has_call_event_set {
-            Core::dispatch_call(current, "exit", option, core.clone());
+        if current.call.is_some() && !core.borrow().mute_calls {
+            Core::dispatch_call(current, "exit", option, core.clone(), true);
         }
 
         if *current.repeat.as_ref().unwrap() {
@@ -385,81 +684,124 @@ impl Core {
         }
     }
 
-    fn dispatch_call(current: &mut MappedEl, way: &str, option: &LocomotiveOption, core: Rc<RefCell<Core>>) {
+    /// The `{namespace}call` DOM event when `dom` is set, and the `on("call")` handlers once one got registered.
+    fn dispatch_call(current: &mut MappedEl, way: &str, option: &LocomotiveOption, core: Rc<RefCell<Core>>, dom: bool) {
         {
             *core.as_ref().borrow().call_way.as_ref().borrow_mut() = way.to_string();
             *core.as_ref().borrow().call_value.as_ref().borrow_mut() = current.call.as_ref().unwrap().split(",").map(|s| s.trim().to_string()).collect::<Vec<String>>();
             *core.as_ref().borrow().call_obj.as_ref().borrow_mut() = Some(current.clone());
         }
 
-        let call_event = Event::new(&format!("{}call", core.as_ref().borrow().namespace.as_ref().borrow())).unwrap();
-        match &option.el {
-            ElementType::Document(doc) => doc.dispatch_event(&call_event).unwrap(),
-            ElementType::Element(el) => el.dispatch_event(&call_event).unwrap(),
+        //same signature as upstream: `(value, way, obj)`
+        let args = core.borrow().call_args();
+
+        let core_ref = core.borrow();
+        let mut pending = core_ref.pending.borrow_mut();
+        if dom {
+            let event_init = CustomEventInit::new();
+            event_init.set_detail(&args);
+            let call_event = CustomEvent::new_with_event_init_dict(&format!("{}call", core_ref.namespace.borrow()), &event_init).unwrap();
+            pending.push(Pending::Dom(option.el.event_target(), call_event.into()));
+        }
+
+        if core_ref.has_call_event_set {
+            pending.push(Pending::Handlers(ListenerEvent::Call, args));
+        }
+    }
+
+    /// `[value, way, obj]` of the last call, value is a string unless several were given.
+    fn call_args(&self) -> Array {
+        let call_value = self.call_value.borrow();
+        let value = match call_value.len() {
+            1 => call_value[0].to_js(),
+            _ => call_value.to_js(),
         };
+        let obj = match self.call_obj.borrow().as_ref() {
+            Some(obj) => obj.to_js_value(),
+            None => JsValue::NULL,
+        };
+
+        Array::of3(&value, &self.call_way.borrow().as_str().into(), &obj)
+    }
+
+    /// `{ value, way, obj }` of the last `data-scroll-call` that fired, `null` if none did yet.
+    pub fn last_call(&self) -> JsValue {
+        if self.call_obj.borrow().is_none() {
+            return JsValue::NULL;
+        }
+
+        let args = self.call_args();
+        let jsobject = JsObject::new();
+        jsobject.set_prop(&"value", &args.get(0));
+        jsobject.set_prop(&"way", &args.get(1));
+        jsobject.set_prop(&"obj", &args.get(2));
+
+        jsobject.into_js_value()
     }
 
     pub fn check_scroll(core: Rc<RefCell<Core>>, options: &LocomotiveOption) {
         Core::dispatch_scroll(core.clone(), options);
     }
 
+    /// The snapshot of the instance is built once and shared by the DOM event and the `on("scroll")` handlers.
     pub fn dispatch_scroll(core: Rc<RefCell<Core>>, options: &LocomotiveOption) {
-        let event = Event::new(&format!("{}scroll", core.as_ref().borrow().namespace.as_ref().borrow())).unwrap();
-        options.el.dispatch_event(&event);
-    }
-}
+        let snapshot = core.borrow().instance.borrow().to_js();
 
-/*
-impl Core {
-
-    fn update_elements(core: Rc<RefCell<Core>>) {
-        {   
-            let core_ref = core.as_ref().borrow();
-            for (id, el) in core_ref.els.as_ref().clone().into_inner().clone().data {
-                let top = el.target_el.as_ref().unwrap().get_bounding_client_rect().top() + core_ref.instance.as_ref().borrow().scroll.y;
-                let html_el = el.target_el.as_ref().unwrap().dyn_ref::<HtmlElement>().unwrap();
-                let bottom = top + html_el.offset_height() as f64;
-                let relative_offset = Core::get_relative_offset(el.offset, core.as_ref().borrow().window_height);
+        let event_init = CustomEventInit::new();
+        event_init.set_detail(&snapshot);
+        let event = CustomEvent::new_with_event_init_dict(&format!("{}scroll", core.as_ref().borrow().namespace.as_ref().borrow()), &event_init).unwrap();
+        {
+            let core_ref = core.borrow();
+            let mut pending = core_ref.pending.borrow_mut();
+            pending.push(Pending::Dom(options.el.event_target(), event.into()));
+            pending.push(Pending::Handlers(ListenerEvent::Scroll, Array::of1(&snapshot)));
+        }
 
-                core_ref.els.as_ref().borrow_mut().data.entry(id).and_modify(|data| {
-                    data.top = top + relative_offset[0];
-                    data.bottom = bottom - relative_offset[1];
-                });
+        Core::flush(&core);
+    }
 
-            }
-        }
-        
-        *core.as_ref().borrow().has_scroll_ticking.borrow_mut() = false;
-        
+    pub fn emit(core: Rc<RefCell<Core>>, event: ListenerEvent) {
+        core.borrow().pending.borrow_mut().push(Pending::Handlers(event, Array::new()));
+        Core::flush(&core);
     }
-}
 
+    /// Run the pending handlers and DOM events, as they may call any method. While the core is borrowed
+    /// up the stack this does nothing, the caller holding it flushes once it let go.
+    pub fn flush(core: &Rc<RefCell<Core>>) {
+        let (pending, listeners) = match core.try_borrow() {
+            Ok(core_ref) => (core_ref.pending.clone(), core_ref.listeners.clone()),
+            Err(_) => return,
+        };
 
-impl Core {
-    fn get_relative_offset(offset: Vec<String>, window_height: f64) -> [f64; 2] {
-        let mut res = [0.0, 0.0];
+        Queue::flush(&pending, core, |item| match item {
+            Pending::Handlers(event, args) => Listeners::emit(&listeners, event, &args),
+            Pending::Dom(target, event) => {
+                let _ = target.dispatch_event(&event);
+            },
+        });
+    }
 
-        if offset.len() > 2 {
-            panic!("offset must be in type of 'x, y' only");
-        }
+    /// Subscribe `handler` to `event`. The first `call` handler also gets the calls of the elements already in view,
+    /// except the ones that do not repeat and already fired.
+    pub fn on(core: Rc<RefCell<Core>>, event: ListenerEvent, handler: Function) {
+        let listeners = core.borrow().listeners.clone();
+        listeners.borrow_mut().add(event, handler);
 
-        for (index, off_set) in offset.iter().enumerate() {
-            let val = match off_set.contains("%") {
-                true => {
-                    let float = off_set.replace("%", "").parse::<f64>().unwrap();
-                    (float * window_height) / 100.0
-                },
-                false => {
-                    off_set.parse::<f64>().unwrap()
-                }
-            };
-            res[index] = val
+        if event == ListenerEvent::Call && !core.borrow().has_call_event_set {
+            {
+                core.borrow_mut().has_call_event_set = true;
+            }
+            let options = core.borrow().scroll.get_option();
+            Core::detect_elements(Some(true), core.clone(), &options);
         }
+    }
 
-        res
+    pub fn off(core: Rc<RefCell<Core>>, event: ListenerEvent, handler: &Function) {
+        let listeners = core.borrow().listeners.clone();
+        listeners.borrow_mut().remove(event, handler);
     }
 }
-*/
+
 
 
 //GETTER
@@ -0,0 +1,80 @@
+//! The instances alive on the page. A nested instance keeps its elements and sections to itself whichever
+//! one got built first, so the enclosing instances re-measure every time one is added or removed inside them.
+
+/// Instances by `name` and container, the handle is whatever re-measures one.
+#[derive(Debug)]
+pub(crate) struct Registry<C, H> {
+    entries: Vec<(String, C, H)>,
+}
+
+impl<C: PartialEq, H: Clone> Registry<C, H> {
+    pub const fn new() -> Self {
+        Registry { entries: Vec::new() }
+    }
+
+    /// Returns the instances with the same `name` whose container `contains` the new one.
+    pub fn add(&mut self, name: &str, container: C, handle: H, contains: impl Fn(&C, &C) -> bool) -> Vec<H> {
+        let enclosing = self.enclosing(name, &container, contains);
+        self.entries.push((name.to_string(), container, handle));
+        enclosing
+    }
+
+    /// Returns the instances with the same `name` whose container `contains` the removed one.
+    pub fn remove(&mut self, name: &str, container: &C, contains: impl Fn(&C, &C) -> bool) -> Vec<H> {
+        self.entries.retain(|(entry_name, entry, _)| entry_name != name || entry != container);
+        self.enclosing(name, container, contains)
+    }
+
+    fn enclosing(&self, name: &str, container: &C, contains: impl Fn(&C, &C) -> bool) -> Vec<H> {
+        self.entries.iter()
+            .filter(|(entry_name, entry, _)| entry_name == name && entry != container && contains(entry, container))
+            .map(|(_, _, handle)| handle.clone())
+            .collect()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //containers as paths in the page
+    fn contains(outer: &&str, inner: &&str) -> bool {
+        inner.starts_with(&format!("{}/", outer))
+    }
+
+    #[test]
+    fn outer_then_inner_remeasures_the_outer() {
+        let mut registry = Registry::new();
+        assert!(registry.add("scroll", "html/main", 1, contains).is_empty());
+        assert_eq!(registry.add("scroll", "html/main/aside", 2, contains), vec![1]);
+        assert_eq!(registry.add("scroll", "html/main/aside/ul", 3, contains), vec![1, 2]);
+    }
+
+    #[test]
+    fn inner_then_outer_has_nothing_to_remeasure() {
+        let mut registry = Registry::new();
+        assert!(registry.add("scroll", "html/main/aside", 2, contains).is_empty());
+        assert!(registry.add("scroll", "html/main", 1, contains).is_empty());
+    }
+
+    #[test]
+    fn only_the_same_name_and_enclosing_containers() {
+        let mut registry = Registry::new();
+        registry.add("scroll", "html/main", 1, contains);
+        registry.add("gallery", "html/main/section", 2, contains);
+        registry.add("scroll", "html/footer", 3, contains);
+        assert_eq!(registry.add("scroll", "html/main/aside", 4, contains), vec![1]);
+        assert_eq!(registry.add("gallery", "html/main/section/div", 5, contains), vec![2]);
+    }
+
+    #[test]
+    fn removing_the_inner_remeasures_the_outer() {
+        let mut registry = Registry::new();
+        registry.add("scroll", "html/main", 1, contains);
+        registry.add("scroll", "html/main/aside", 2, contains);
+        assert_eq!(registry.remove("scroll", &"html/main/aside", contains), vec![1]);
+        assert!(registry.remove("scroll", &"html/main", contains).is_empty());
+        assert!(registry.add("scroll", "html/main/aside", 2, contains).is_empty());
+    }
+}
@@ -1,16 +1,10 @@
-use crate::option::LocomotiveOption;
+use crate::{option::LocomotiveOption, error::LocomotiveError};
 
 use super::Core;
 
 impl Core {
-    pub fn check_smooth_scroll(options: &LocomotiveOption) -> bool {
-        if (options.smooth && !options.is_mobile)  || 
-        (options.tablet.as_ref().unwrap().smooth && options.is_tablet) ||
-        (options.smartphone.as_ref().unwrap().smooth && options.is_mobile && !options.is_tablet) {
-            true
-        } else {
-            false
-        }
+    pub fn check_smooth_scroll(options: &LocomotiveOption) -> Result<bool, LocomotiveError> {
+        Ok(options.context_option(options.current_context())?.smooth)
     }
     
 }
\ No newline at end of file
@@ -0,0 +1,483 @@
+//! The scroll math, kept free of the DOM so it runs under `cargo test`.
+//! Everything is in px on the page, `axis` is the `direction_axis` of the core, 'x' or 'y'.
+
+use crate::{option::{Position, ParallaxPosition}, UP, DOWN, LEFT, RIGHT, SPACE, PAGEUP, PAGEDOWN, HOME, END};
+
+
+/// Bounds of an element on the page.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rect {
+    pub top: f64,
+    pub bottom: f64,
+    pub left: f64,
+    pub right: f64,
+}
+
+impl Rect {
+    pub fn new(top: f64, bottom: f64, left: f64, right: f64) -> Self {
+        Rect { top, bottom, left, right }
+    }
+
+    pub fn start(&self, axis: char) -> f64 {
+        match axis {
+            'x' => self.left,
+            _ => self.top,
+        }
+    }
+
+    pub fn end(&self, axis: char) -> f64 {
+        match axis {
+            'x' => self.right,
+            _ => self.bottom,
+        }
+    }
+
+    pub fn middle(&self) -> Position {
+        Position::new((self.right - self.left) / 2.0 + self.left, (self.bottom - self.top) / 2.0 + self.top)
+    }
+
+    /// Narrow the rect on `axis`, `offset` is `[start, end]` in px.
+    pub fn with_offset(mut self, offset: [f64; 2], axis: char) -> Self {
+        match axis {
+            'x' => {
+                self.left += offset[0];
+                self.right -= offset[1];
+            },
+            _ => {
+                self.top += offset[0];
+                self.bottom -= offset[1];
+            }
+        }
+        self
+    }
+}
+
+/// The part of the page on screen.
+#[derive(Clone, Debug)]
+pub struct Viewport {
+    pub scroll: Position,
+    /// Window width and height.
+    pub size: Position,
+    pub limit: Position,
+    pub axis: char,
+}
+
+impl Viewport {
+    pub fn start(&self) -> f64 {
+        self.scroll.get(self.axis)
+    }
+
+    pub fn end(&self) -> f64 {
+        self.start() + self.size.get(self.axis)
+    }
+
+    pub fn middle(&self) -> f64 {
+        self.start() + self.size.get(self.axis) / 2.0
+    }
+
+    pub fn limit(&self) -> f64 {
+        self.limit.get(self.axis)
+    }
+}
+
+
+/// `data-scroll-offset` values in px, percentages are relative to `window_size`.
+pub fn relative_offset(offset: &[String], window_size: f64) -> [f64; 2] {
+    let mut res = [0.0, 0.0];
+
+    for (index, val) in offset.iter().take(2).enumerate() {
+        let val = val.trim();
+        res[index] = match val.strip_suffix('%') {
+            Some(percent) => percent.trim().parse::<f64>().unwrap_or(0.0) * window_size / 100.0,
+            None => val.parse::<f64>().unwrap_or(0.0)
+        };
+    }
+
+    res
+}
+
+/// Keep a scroll position within `[0, limit]`.
+pub fn clamp(value: f64, limit: f64) -> f64 {
+    value.min(limit).max(0.0)
+}
+
+/// Where a key press sends `delta`, `None` for the keys that don't scroll.
+pub fn key_target(key_code: u32, shift: bool, axis: char, delta: f64, key_step: f64, page_step: f64, limit: f64) -> Option<f64> {
+    let step = match key_code {
+        UP => -key_step,
+        DOWN => key_step,
+        LEFT if axis == 'x' => -key_step,
+        RIGHT if axis == 'x' => key_step,
+        PAGEUP => -page_step,
+        PAGEDOWN => page_step,
+        SPACE if shift => -page_step,
+        SPACE => page_step,
+        HOME => -limit,
+        END => limit,
+        _ => return None
+    };
+    Some(clamp(delta + step, limit))
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ViewChange {
+    Enter,
+    Exit,
+}
+
+/// Outcome of `detect` for one element.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Detection {
+    pub change: Option<ViewChange>,
+    /// From 0 when the element appears to 1 when it leaves, only while in view.
+    pub progress: Option<f64>,
+}
+
+/// Whether an element enters or leaves the viewport. With `recall` an element already in view
+/// enters again, so its `call` gets dispatched once more.
+pub fn detect(rect: &Rect, in_view: bool, recall: bool, viewport: &Viewport) -> Detection {
+    let axis = viewport.axis;
+    let (start, end) = (rect.start(axis), rect.end(axis));
+    let (scroll_start, scroll_end) = (viewport.start(), viewport.end());
+
+    let mut change = None;
+    let mut in_view = in_view;
+    if (!in_view || recall) && scroll_end >= start && scroll_start < end {
+        change = Some(ViewChange::Enter);
+        in_view = true;
+    }
+
+    let mut progress = None;
+    if in_view {
+        let size = viewport.size.get(axis);
+        progress = Some((scroll_start - (start - size)) / (end - start + size));
+
+        if scroll_end < start || scroll_start > end {
+            change = Some(ViewChange::Exit);
+        }
+    }
+
+    Detection { change, progress }
+}
+
+/// Whether a section is shown, sections out of the viewport are hidden and not translated.
+pub fn section_in_view(start: f64, end: f64, persistent: bool, scroll: f64) -> bool {
+    persistent || (scroll > start && scroll < end)
+}
+
+
+/// What `parallax_distance` needs to know about an element.
+#[derive(Clone, Debug)]
+pub struct Parallax {
+    pub rect: Rect,
+    pub middle: Position,
+    pub speed: f64,
+    /// Relative to the middle of the viewport when `None`.
+    pub position: Option<ParallaxPosition>,
+    pub sticky: bool,
+    pub in_view: bool,
+}
+
+/// Translation of a parallax or sticky element, `None` leaves the element where it is.
+/// `forced` starts from 0 instead, `all` moves the elements out of view too.
+pub fn parallax_distance(el: &Parallax, viewport: &Viewport, forced: bool, all: bool) -> Option<f64> {
+    let mut distance = if forced { Some(0.0) } else { None };
+    let (scroll, size, axis) = (&viewport.scroll, &viewport.size, viewport.axis);
+    let scroll_right = scroll.x + size.x;
+    let scroll_bottom = scroll.y + size.y;
+
+    if el.in_view || all {
+        let speed = el.speed;
+        distance = Some(match el.position {
+            Some(ParallaxPosition::Top | ParallaxPosition::Left) => viewport.start() * -speed,
+            Some(ParallaxPosition::ElementTop) => (scroll_bottom - el.rect.top) * speed,
+            Some(ParallaxPosition::Bottom) => (viewport.limit() - scroll_bottom + size.y) * speed,
+            Some(ParallaxPosition::ElementLeft) => (scroll_right - el.rect.left) * -speed,
+            //same as upstream, the window height and not its width
+            Some(ParallaxPosition::Right) => (viewport.limit() - scroll_right + size.y) * speed,
+            None => (viewport.middle() - el.middle.get(axis)) * -speed,
+        });
+    }
+
+    if el.sticky {
+        let (scroll, start, end, size) = (viewport.start(), el.rect.start(axis), el.rect.end(axis), size.get(axis));
+        distance = if el.in_view {
+            Some(scroll - start + size)
+        } else if scroll < start - size {
+            Some(0.0)
+        } else if scroll > end + 100.0 {
+            Some(end - start + size)
+        } else {
+            None
+        };
+    }
+
+    distance
+}
+
+
+/// `"down"`/`"up"` or `"right"`/`"left"` when the position changed on `axis`.
+pub fn direction(previous: f64, current: f64, axis: char) -> Option<&'static str> {
+    let (forward, backward) = match axis {
+        'x' => ("right", "left"),
+        _ => ("down", "up")
+    };
+
+    if current > previous {
+        Some(forward)
+    } else if current < previous {
+        Some(backward)
+    } else {
+        None
+    }
+}
+
+/// In px/ms, `elapsed` is floored at 1ms.
+pub fn speed(previous: f64, current: f64, elapsed: f64) -> f64 {
+    if current == previous {
+        return 0.0;
+    }
+    (current - previous) / elapsed.max(1.0)
+}
+
+
+/// Length of the thumb in a `track` px long scrollbar.
+pub fn thumb_size(track: f64, limit: f64) -> f64 {
+    (track * track) / (limit + track)
+}
+
+/// Translation of the thumb, `thumb_limit` being the track length minus the thumb length.
+pub fn thumb_translation(scroll: f64, limit: f64, thumb_limit: f64) -> f64 {
+    if limit <= 0.0 {
+        return 0.0;
+    }
+    (scroll / limit) * thumb_limit
+}
+
+/// Scroll position for a pointer `pointer` px into the track, `None` past either end.
+pub fn scrollbar_drag(pointer: f64, track: f64, limit: f64) -> Option<f64> {
+    let position = (pointer / track) * limit;
+    (position > 0.0 && position < limit).then_some(position)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::option::OptionValue;
+
+    const EPSILON: f64 = 0.000001;
+
+    fn viewport(scroll_y: f64) -> Viewport {
+        Viewport { scroll: Position::new(0.0, scroll_y), size: Position::new(1280.0, 800.0), limit: Position::new(0.0, 5000.0), axis: 'y' }
+    }
+
+    fn horizontal(scroll_x: f64) -> Viewport {
+        Viewport { scroll: Position::new(scroll_x, 0.0), size: Position::new(1280.0, 800.0), limit: Position::new(8000.0, 0.0), axis: 'x' }
+    }
+
+    /// Anything but a `data-scroll-position` value follows the middle.
+    fn parallax(rect: Rect, speed: f64, position: &str) -> Parallax {
+        Parallax { rect, middle: rect.middle(), speed, position: ParallaxPosition::parse(position).ok(), sticky: false, in_view: true }
+    }
+
+    #[test]
+    fn rect_offset_narrows_the_scroll_axis() {
+        let rect = Rect::new(1000.0, 1200.0, 0.0, 500.0);
+        assert_eq!(rect.with_offset([100.0, 50.0], 'y'), Rect::new(1100.0, 1150.0, 0.0, 500.0));
+        assert_eq!(rect.with_offset([100.0, 50.0], 'x'), Rect::new(1000.0, 1200.0, 100.0, 450.0));
+        assert_eq!(rect.middle(), Position::new(250.0, 1100.0));
+    }
+
+    #[test]
+    fn relative_offset_reads_px_and_percentages() {
+        let offset = |values: &[&str]| values.iter().map(|val| val.to_string()).collect::<Vec<_>>();
+        assert_eq!(relative_offset(&offset(&["100", "25%"]), 800.0), [100.0, 200.0]);
+        assert_eq!(relative_offset(&offset(&[" 10% "]), 800.0), [80.0, 0.0]);
+        assert_eq!(relative_offset(&offset(&["abc", "1", "2"]), 800.0), [0.0, 1.0]);
+        assert_eq!(relative_offset(&[], 800.0), [0.0, 0.0]);
+    }
+
+    #[test]
+    fn clamps_to_the_limit() {
+        assert_eq!(clamp(-10.0, 500.0), 0.0);
+        assert_eq!(clamp(250.0, 500.0), 250.0);
+        assert_eq!(clamp(900.0, 500.0), 500.0);
+        //Content shorter than the window
+        assert_eq!(clamp(10.0, -100.0), 0.0);
+    }
+
+    #[test]
+    fn keys_move_within_the_limit() {
+        let key = |key_code: u32, shift: bool, axis: char, delta: f64, limit: f64| key_target(key_code, shift, axis, delta, 240.0, 800.0, limit);
+        assert_eq!(key(DOWN, false, 'y', 100.0, 5000.0), Some(340.0));
+        assert_eq!(key(UP, false, 'y', 100.0, 5000.0), Some(0.0));
+        assert_eq!(key(SPACE, true, 'y', 1000.0, 5000.0), Some(200.0));
+        assert_eq!(key(PAGEDOWN, false, 'y', 4500.0, 5000.0), Some(5000.0));
+        assert_eq!(key(HOME, false, 'y', 4500.0, 5000.0), Some(0.0));
+        assert_eq!(key(END, false, 'y', 0.0, 5000.0), Some(5000.0));
+        assert_eq!(key(RIGHT, false, 'x', 0.0, 5000.0), Some(240.0));
+        assert_eq!(key(RIGHT, false, 'y', 0.0, 5000.0), None);
+        assert_eq!(key(9, false, 'y', 0.0, 5000.0), None);
+        //Content shorter than the window
+        assert_eq!(key(DOWN, false, 'y', 0.0, -100.0), Some(0.0));
+        assert_eq!(key(END, false, 'y', 0.0, -100.0), Some(0.0));
+    }
+
+    #[test]
+    fn enters_when_the_viewport_reaches_the_element() {
+        let rect = Rect::new(1000.0, 1200.0, 0.0, 1280.0);
+        assert_eq!(detect(&rect, false, false, &viewport(0.0)), Detection { change: None, progress: None });
+
+        let detection = detect(&rect, false, false, &viewport(200.0));
+        assert_eq!(detection.change, Some(ViewChange::Enter));
+        assert!(detection.progress.unwrap().abs() < EPSILON);
+    }
+
+    #[test]
+    fn stays_in_view_without_a_change() {
+        let rect = Rect::new(1000.0, 1200.0, 0.0, 1280.0);
+        let detection = detect(&rect, true, false, &viewport(600.0));
+        assert_eq!(detection.change, None);
+        assert!((detection.progress.unwrap() - 0.4).abs() < EPSILON);
+    }
+
+    #[test]
+    fn recall_enters_again() {
+        let rect = Rect::new(1000.0, 1200.0, 0.0, 1280.0);
+        assert_eq!(detect(&rect, true, true, &viewport(600.0)).change, Some(ViewChange::Enter));
+        assert_eq!(detect(&rect, false, true, &viewport(0.0)).change, None);
+    }
+
+    #[test]
+    fn exits_once_scrolled_past() {
+        let rect = Rect::new(1000.0, 1200.0, 0.0, 1280.0);
+        let detection = detect(&rect, true, false, &viewport(1201.0));
+        assert_eq!(detection.change, Some(ViewChange::Exit));
+        assert!(detection.progress.unwrap() > 1.0);
+
+        assert_eq!(detect(&rect, true, false, &viewport(100.0)).change, Some(ViewChange::Exit));
+        //Exactly at the bottom edge is neither
+        assert_eq!(detect(&rect, true, false, &viewport(1200.0)).change, None);
+        assert_eq!(detect(&rect, false, false, &viewport(1200.0)).change, None);
+    }
+
+    #[test]
+    fn progress_goes_from_0_to_1() {
+        let rect = Rect::new(1000.0, 1200.0, 0.0, 1280.0);
+        let mut last = -1.0;
+        for scroll in (200..=1200).step_by(50) {
+            let progress = detect(&rect, true, false, &viewport(scroll as f64)).progress.unwrap();
+            assert!(progress > last);
+            last = progress;
+        }
+        assert!((last - 1.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn detects_on_the_x_axis() {
+        let rect = Rect::new(0.0, 800.0, 2000.0, 2500.0);
+        assert_eq!(detect(&rect, false, false, &horizontal(0.0)).change, None);
+        assert_eq!(detect(&rect, false, false, &horizontal(800.0)).change, Some(ViewChange::Enter));
+        assert_eq!(detect(&rect, true, false, &horizontal(2600.0)).change, Some(ViewChange::Exit));
+    }
+
+    #[test]
+    fn sections_show_between_their_bounds() {
+        assert!(!section_in_view(1000.0, 2000.0, false, 1000.0));
+        assert!(section_in_view(1000.0, 2000.0, false, 1500.0));
+        assert!(!section_in_view(1000.0, 2000.0, false, 2000.0));
+        assert!(section_in_view(1000.0, 2000.0, true, 0.0));
+    }
+
+    #[test]
+    fn parallax_follows_the_position() {
+        let rect = Rect::new(1000.0, 1200.0, 100.0, 300.0);
+        let viewport = viewport(600.0);
+        assert_eq!(parallax_distance(&parallax(rect, 0.2, "top"), &viewport, false, false), Some(-120.0));
+        assert_eq!(parallax_distance(&parallax(rect, 0.2, "elementTop"), &viewport, false, false), Some(80.0));
+        assert_eq!(parallax_distance(&parallax(rect, 0.2, "bottom"), &viewport, false, false), Some(880.0));
+        //Middle of the viewport at 1000, middle of the element at 1100
+        assert_eq!(parallax_distance(&parallax(rect, 0.2, ""), &viewport, false, false), Some(20.0));
+    }
+
+    #[test]
+    fn parallax_on_the_x_axis() {
+        let rect = Rect::new(0.0, 800.0, 2000.0, 2400.0);
+        let viewport = horizontal(1000.0);
+        assert_eq!(parallax_distance(&parallax(rect, 0.5, "left"), &viewport, false, false), Some(-500.0));
+        assert_eq!(parallax_distance(&parallax(rect, 0.5, "elementLeft"), &viewport, false, false), Some(-140.0));
+        assert_eq!(parallax_distance(&parallax(rect, 0.5, "right"), &viewport, false, false), Some(3260.0));
+        assert_eq!(parallax_distance(&parallax(rect, 0.5, "middle"), &viewport, false, false), Some(280.0));
+    }
+
+    #[test]
+    fn parallax_out_of_view_is_left_alone() {
+        let rect = Rect::new(3000.0, 3200.0, 0.0, 1280.0);
+        let mut el = parallax(rect, 0.2, "top");
+        el.in_view = false;
+        assert_eq!(parallax_distance(&el, &viewport(0.0), false, false), None);
+        assert_eq!(parallax_distance(&el, &viewport(0.0), true, false), Some(0.0));
+        assert_eq!(parallax_distance(&el, &viewport(100.0), false, true), Some(-20.0));
+    }
+
+    #[test]
+    fn sticky_follows_the_scroll_while_in_view() {
+        //Sticky rects start a window lower, see `add_elements`
+        let rect = Rect::new(1800.0, 2600.0, 0.0, 1280.0);
+        let mut el = parallax(rect, 0.0, "");
+        el.sticky = true;
+
+        assert_eq!(parallax_distance(&el, &viewport(1500.0), false, false), Some(500.0));
+        assert_eq!(parallax_distance(&el, &viewport(1600.0), false, false), Some(600.0));
+    }
+
+    #[test]
+    fn sticky_rests_at_either_end_out_of_view() {
+        let rect = Rect::new(1800.0, 2600.0, 0.0, 1280.0);
+        let mut el = parallax(rect, 0.0, "");
+        el.sticky = true;
+        el.in_view = false;
+
+        assert_eq!(parallax_distance(&el, &viewport(500.0), false, false), Some(0.0));
+        assert_eq!(parallax_distance(&el, &viewport(2800.0), false, false), Some(1600.0));
+        //Close to the element, it stays where it is
+        assert_eq!(parallax_distance(&el, &viewport(1200.0), false, false), None);
+        assert_eq!(parallax_distance(&el, &viewport(2650.0), false, false), None);
+    }
+
+    #[test]
+    fn direction_and_speed() {
+        assert_eq!(direction(100.0, 200.0, 'y'), Some("down"));
+        assert_eq!(direction(200.0, 100.0, 'y'), Some("up"));
+        assert_eq!(direction(100.0, 200.0, 'x'), Some("right"));
+        assert_eq!(direction(200.0, 100.0, 'x'), Some("left"));
+        assert_eq!(direction(100.0, 100.0, 'y'), None);
+
+        assert_eq!(speed(100.0, 200.0, 50.0), 2.0);
+        assert_eq!(speed(100.0, 200.0, 0.0), 100.0);
+        assert_eq!(speed(100.0, 100.0, 50.0), 0.0);
+    }
+
+    #[test]
+    fn scrollbar_thumb() {
+        let (track, limit) = (800.0, 3200.0);
+        let thumb = thumb_size(track, limit);
+        assert_eq!(thumb, 160.0);
+
+        let thumb_limit = track - thumb;
+        assert_eq!(thumb_translation(0.0, limit, thumb_limit), 0.0);
+        assert_eq!(thumb_translation(1600.0, limit, thumb_limit), 320.0);
+        assert_eq!(thumb_translation(limit, limit, thumb_limit), thumb_limit);
+        assert_eq!(thumb_translation(100.0, 0.0, thumb_limit), 0.0);
+    }
+
+    #[test]
+    fn scrollbar_drag_maps_the_track_to_the_page() {
+        assert_eq!(scrollbar_drag(400.0, 800.0, 3200.0), Some(1600.0));
+        assert_eq!(scrollbar_drag(0.0, 800.0, 3200.0), None);
+        assert_eq!(scrollbar_drag(-20.0, 800.0, 3200.0), None);
+        assert_eq!(scrollbar_drag(800.0, 800.0, 3200.0), None);
+    }
+}
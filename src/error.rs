@@ -0,0 +1,132 @@
+use std::fmt;
+
+use convert_js::ToJs;
+use js_sys::{Error, Reflect};
+use wasm_bindgen::JsValue;
+
+/// Why an instance cannot be built, thrown from `new LocomotiveScroll(...)`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LocomotiveError {
+    /// The options object does not match the expected shape, or one of its values is not valid.
+    InvalidOptions(String),
+    /// Nothing matches `query`.
+    ContainerNotFound(String),
+    /// A `data-*` attribute holds a value that cannot be used, keyed by its dataset name.
+    InvalidAttribute { name: String, value: String, expected: String },
+    /// No options for the detected device context.
+    UnsupportedContext(String),
+}
+
+impl LocomotiveError {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LocomotiveError::InvalidOptions(_) => "invalidOptions",
+            LocomotiveError::ContainerNotFound(_) => "containerNotFound",
+            LocomotiveError::InvalidAttribute { .. } => "invalidAttribute",
+            LocomotiveError::UnsupportedContext(_) => "unsupportedContext",
+        }
+    }
+}
+
+/// `scrollSpeed` -> `data-scroll-speed`
+fn attribute_name(name: &str) -> String {
+    let mut attribute = String::from("data-");
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            attribute.push('-');
+        }
+        attribute.push(c.to_ascii_lowercase());
+    }
+    attribute
+}
+
+impl fmt::Display for LocomotiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LocomotiveError::InvalidOptions(reason) => write!(f, "invalid options: {reason}"),
+            LocomotiveError::ContainerNotFound(query) => write!(f, "no scroll container matches \"{query}\""),
+            LocomotiveError::InvalidAttribute { name, value, expected } => write!(f, "invalid value \"{value}\" for [{}], expected {expected}", attribute_name(name)),
+            LocomotiveError::UnsupportedContext(context) => write!(f, "no options for the \"{context}\" context"),
+        }
+    }
+}
+
+impl std::error::Error for LocomotiveError {}
+
+/// A string option or attribute that is none of its allowed values.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnknownValue {
+    pub value: String,
+    pub allowed: Vec<&'static str>,
+}
+
+impl UnknownValue {
+    /// `"a", "b" or "c"`
+    pub fn expected(&self) -> String {
+        let quoted = self.allowed.iter().map(|value| format!("\"{value}\"")).collect::<Vec<_>>();
+        match quoted.split_last() {
+            Some((last, [])) => last.clone(),
+            Some((last, rest)) => format!("{} or {last}", rest.join(", ")),
+            None => String::new(),
+        }
+    }
+
+    pub fn into_attribute_error(self, name: &str) -> LocomotiveError {
+        let expected = self.expected();
+        LocomotiveError::InvalidAttribute { name: name.to_string(), value: self.value, expected }
+    }
+}
+
+impl fmt::Display for UnknownValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "expected {}, got \"{}\"", self.expected(), self.value)
+    }
+}
+
+impl std::error::Error for UnknownValue {}
+
+impl ToJs for LocomotiveError {
+    fn to_js(&self) -> JsValue {
+        let error = Error::new(&self.to_string());
+        error.set_name("LocomotiveError");
+        let _ = Reflect::set(&error, &"reason".into(), &self.as_str().into());
+
+        error.into()
+    }
+}
+
+impl From<LocomotiveError> for JsValue {
+    fn from(err: LocomotiveError) -> Self {
+        err.to_js()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attribute_names_are_kebab_cased() {
+        assert_eq!(attribute_name("scrollSpeed"), "data-scroll-speed");
+        assert_eq!(attribute_name("scroll"), "data-scroll");
+    }
+
+    #[test]
+    fn messages_name_the_faulty_value() {
+        let err = LocomotiveError::InvalidAttribute { name: "scrollSpeed".to_string(), value: "fast".to_string(), expected: "a number".to_string() };
+        assert_eq!(err.to_string(), "invalid value \"fast\" for [data-scroll-speed], expected a number");
+        assert_eq!(LocomotiveError::ContainerNotFound("[data-scroll-container]".to_string()).to_string(), "no scroll container matches \"[data-scroll-container]\"");
+        assert_eq!(LocomotiveError::UnsupportedContext("tablet".to_string()).as_str(), "unsupportedContext");
+    }
+
+    #[test]
+    fn unknown_values_list_the_allowed_ones() {
+        let err = UnknownValue { value: "diagonal".to_string(), allowed: vec!["vertical", "horizontal", "both"] };
+        assert_eq!(err.to_string(), "expected \"vertical\", \"horizontal\" or \"both\", got \"diagonal\"");
+        assert_eq!(UnknownValue { value: String::new(), allowed: vec!["top"] }.expected(), "\"top\"");
+
+        let err = err.into_attribute_error("scrollDirection");
+        assert_eq!(err.to_string(), "invalid value \"diagonal\" for [data-scroll-direction], expected \"vertical\", \"horizontal\" or \"both\"");
+    }
+}
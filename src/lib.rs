@@ -1,3 +1,6 @@
+//Closures are stored as `Rc<RefCell<Option<Closure<..>>>>` throughout
+#![allow(clippy::type_complexity)]
+
 mod option;
 mod native;
 mod smooth;
@@ -7,18 +10,21 @@ mod virtual_scroll;
 mod smooth_scroll;
 mod utils;
 mod bezier_easing;
+mod engine;
+mod error;
 
+use js_sys::{Promise, Function};
 use wasm_bindgen::{prelude::wasm_bindgen, JsValue, JsCast};
 pub use web_sys::*;
 
 
 use std::{panic, cell::RefCell, rc::Rc};
 
-use crate::core::Core;
-use option::LocomotiveOption;
+use crate::{core::Core, error::LocomotiveError};
+use option::{LocomotiveOption, UpdateOption, Position};
 use scroll::Scroll;
+use utils::{els::{ScrollToOption, ScrollToTarget, ScrollToPromise, ScrollToInterrupt}, listeners::{ListenerEvent, Listeners}};
 //use virtual_scroll::{VirtualScroll, VsOption};
-use web_sys::{console, window};
 
 
 
@@ -39,87 +45,215 @@ pub const END: u32 = 35;
 #[derive(Clone, Debug)]
 #[wasm_bindgen]
 pub struct LocomotiveScroll {
-    core: Rc<RefCell<Core>>,
+    core: RefCell<Option<Rc<RefCell<Core>>>>,
 }
 
 #[wasm_bindgen]
 impl LocomotiveScroll {
+    /// Several instances can share a page, each on its own `el` (or `query`) with its own `namespace`
+    /// and `scrollBarContainer`. An instance leaves the elements of an instance nested in it alone.
+    /// Throws a `LocomotiveError` whose `reason` is "invalidOptions", "containerNotFound",
+    /// "invalidAttribute" or "unsupportedContext".
     #[wasm_bindgen(constructor)]
-    pub fn new(options: JsValue) -> Self {
+    pub fn new(options: JsValue) -> Result<LocomotiveScroll, JsValue> {
         panic::set_hook(Box::new(console_error_panic_hook::hook));
-        let mut options: LocomotiveOption = serde_wasm_bindgen::from_value(options).unwrap();
-        options.init();
+        let options = LocomotiveOption::from_js(&options)?;
+        let core = LocomotiveScroll::build(options, None)?;
 
-        //warnings
-        if !options.smooth && options.direction == "horizontal".to_string() {
-            console::warn_1(&"'Smooth: false' & 'horizontal' direction are not yet compatible".into());
+        Ok(LocomotiveScroll { core: RefCell::new(Some(core)) })
+    }
+
+    /// Merge `options` into the current ones, e.g. `setOptions({ lerp: 0.05 })`.
+    /// Most options apply right away. `direction`, `name`, `smooth`, `el` and the few others only read
+    /// while building the instance re-create it in place, keeping the position and the `on` handlers.
+    /// Throws a `LocomotiveError` like the constructor, the previous options stay in use then.
+    /// If those can't be built again either, the instance is left destroyed and that second error is logged.
+    #[wasm_bindgen(js_name = setOptions)]
+    pub fn set_options(&self, options: JsValue) -> Result<(), JsValue> {
+        let core = match self.core() {
+            Some(core) => core,
+            None => return Ok(()),
+        };
+        let current = core.borrow().scroll.get_option();
+        let mut options = current.merge(&options)?;
+        options.detect_context()?;
+
+        if !current.needs_reinit(&options) {
+            *core.borrow().scroll.get_mut_option() = options;
+            Core::update(core, &UpdateOption { preserve_scroll: true })?;
+            return Ok(());
         }
-        if let Some(tablet) = &options.tablet {
-            if !tablet.smooth && tablet.direction == "horizontal".to_string() {
-                console::warn_1(&"'Smooth: false' & 'horizontal' direction are not yet compatible(Tablet)".into());
+
+        let (scroll, listeners) = {
+            let core = core.borrow();
+            let scroll = core.instance.borrow().scroll.clone();
+            (scroll, (core.listeners.clone(), core.has_call_event_set))
+        };
+        self.destroy();
+
+        match LocomotiveScroll::rebuild(options, &scroll, listeners.clone()) {
+            Ok(core) => {
+                *self.core.borrow_mut() = Some(core);
+                Ok(())
+            },
+            Err(err) => {
+                match LocomotiveScroll::rebuild(current, &scroll, listeners) {
+                    Ok(core) => *self.core.borrow_mut() = Some(core),
+                    Err(fallback) => console::error_1(&fallback.into()),
+                }
+                Err(err.into())
             }
         }
-        if let Some(smarthphone) = &options.smartphone {
-            if !smarthphone.smooth && smarthphone.direction == "horizontal".to_string() {
-                console::warn_1(&"'Smooth: false' & 'horizontal' direction are not yet compatible(Smartphone)".into());
+    }
+
+    /// `target` can be a selector, an `Element`, a number or one of "top", "bottom", "left", "right".
+    /// `easing` can be a preset such as "ease-in-out" or "easeOutExpo", a CSS `cubic-bezier()`, `steps()`
+    /// or `linear()` string, an array of four control points or a function `(t) => number`.
+    /// The returned promise resolves with the final position, or rejects with an error whose
+    /// `reason` is "wheel", "scrollbar", "keyboard", "cancelled", "stopped", "destroyed", "invalidTarget" or "invalidEasing".
+    #[wasm_bindgen(js_name = scrollTo)]
+    pub fn scroll_to(&self, target: JsValue, options: JsValue) -> Promise {
+        let target = match ScrollToTarget::from_js(target) {
+            Some(target) => target,
+            None => return ScrollToPromise::rejected(ScrollToInterrupt::InvalidTarget),
+        };
+        let scroll_to_option = match ScrollToOption::from_js(&options) {
+            Ok(option) => option,
+            Err(reason) => return ScrollToPromise::rejected(reason),
+        };
+        let core = match self.core() {
+            Some(core) => core,
+            None => return ScrollToPromise::rejected(ScrollToInterrupt::Destroyed),
+        };
+
+        let (scroll, options) = {
+            let core = core.borrow();
+            (core.scroll.clone(), core.scroll.get_option())
+        };
+        Core::scroll_to(scroll, target, scroll_to_option, core, &options)
+    }
+
+    /// Resume scrolling after `stop`.
+    pub fn start(&self) {
+        if let Some(core) = self.core() {
+            Core::start_scroll(core);
+        }
+    }
+
+    /// Freeze the scroll, e.g. while a modal is open. Adds `stopClass` on `<html>`.
+    pub fn stop(&self) {
+        if let Some(core) = self.core() {
+            Core::stop_scroll(core);
+        }
+    }
+
+    /// Subscribe to "scroll", "call", "scrollStart", "scrollEnd", "resize" or "contextChange".
+    /// "scroll" handlers get the instance, "call" handlers get `(value, way, obj)`,
+    /// "contextChange" handlers get `{ context, previous, smooth }`.
+    pub fn on(&self, event: &str, handler: Function) {
+        let event = match ListenerEvent::from_name(event) {
+            Some(event) => event,
+            None => {
+                console::warn_1(&format!("'{}' event is not supported", event).into());
+                return;
             }
+        };
+        if let Some(core) = self.core() {
+            Core::on(core, event, handler);
         }
+    }
 
-        //Check mobile and tablet
-        options.check_mobile();
-        options.check_tablet();
+    pub fn off(&self, event: &str, handler: Function) {
+        if let (Some(event), Some(core)) = (ListenerEvent::from_name(event), self.core()) {
+            Core::off(core, event, &handler);
+        }
+    }
 
-        //core
-        let core = Core::new(options.clone());
+    /// The last call as `{ value, way, obj }`, or `null` if no call fired yet.
+    #[wasm_bindgen(js_name = lastCall)]
+    pub fn last_call(&self) -> JsValue {
+        match self.core() {
+            Some(core) => core.borrow().last_call(),
+            None => JsValue::NULL,
+        }
+    }
 
+    /// Re-measure sections and elements after the layout changed.
+    /// Pass `{ preserveScroll: true }` to keep the position even if it is past the new limit.
+    /// Throws a `LocomotiveError` if an element got an invalid `data-scroll-*` value.
+    pub fn update(&self, options: JsValue) -> Result<(), JsValue> {
+        if let Some(core) = self.core() {
+            Core::update(core, &UpdateOption::from_js(&options))?;
+        }
+        Ok(())
+    }
+
+    /// Remove every listener, class and DOM node added by this instance and free it.
+    /// Any other method becomes a no-op afterwards.
+    pub fn destroy(&self) {
+        let core = self.core.borrow_mut().take();
+        if let Some(core) = core {
+            Core::destroy(core);
+        }
+    }
+ 
+}
 
-        //Smooth
-        let mut smooth = LocomotiveScroll {core};
+impl LocomotiveScroll {
+    /// `None` once the instance got destroyed.
+    fn core(&self) -> Option<Rc<RefCell<Core>>> {
+        self.core.borrow().clone()
+    }
 
-        Core::check_scroll_callback(smooth.core.clone());
-        Core::check_resize_callback(smooth.core.clone());
-        Core::check_event_callback(smooth.core.clone());
+    /// `listeners` are the handlers and `has_call_event_set` of a previous build, they get the events of `init` too.
+    fn build(mut options: LocomotiveOption, listeners: Option<(Rc<RefCell<Listeners>>, bool)>) -> Result<Rc<RefCell<Core>>, LocomotiveError> {
+        options.init()?;
+
+        options.detect_context()?;
+
+        //core
+        let core = Core::new(options)?;
+
+
+        Core::check_scroll_callback(core.clone());
+        Core::check_resize_callback(core.clone());
         {
             let window = window().unwrap();
-            let check_resize_cb = Core::get_check_resize(smooth.core.clone());
+            let check_resize_cb = Core::get_check_resize(core.clone());
             let check_resize_cb = check_resize_cb.borrow();
             let check_resize_cb = check_resize_cb.as_ref().unwrap();
             window.add_event_listener_with_callback_and_bool("resize", check_resize_cb.as_ref().unchecked_ref(), false).unwrap();  
         }
-        
-        //let smooth_dbg = format!("{:?}", smooth);
-        //console::log_1(&smooth_dbg.into());
-
 
+        if let Some((listeners, has_call_event_set)) = listeners {
+            let mut core_mut = core.borrow_mut();
+            core_mut.listeners = listeners;
+            core_mut.has_call_event_set = has_call_event_set;
+        }
 
-        //INIT
-        smooth.init();
-
-        //let smooth_dbg = format!("{:?}", smooth);
-        //console::log_1(&smooth_dbg.into());
+        //INIT, also scrolls to the URL hash once the page is laid out
+        if let Err(err) = Core::init(core.clone()) {
+            //nothing half built stays on the page
+            Core::destroy(core);
+            return Err(err);
+        }
+        Core::register(&core);
 
-        smooth
+        Ok(core)
     }
 
+    /// Build again after `setOptions`, from where the previous instance was.
+    fn rebuild(mut options: LocomotiveOption, scroll: &Position, listeners: (Rc<RefCell<Listeners>>, bool)) -> Result<Rc<RefCell<Core>>, LocomotiveError> {
+        options.init_position = scroll.clone();
+        let core = LocomotiveScroll::build(options, Some(listeners))?;
 
-    fn init(&mut self) {
-
-        //init core
-
-        Core::init(self.core.clone());
-    
-        if let Ok(hash) = window().unwrap().location().hash() {
-            //Get the hash without the `#`
-            let id = hash.replace("#", "");
-
-            //if element found, scroll to it.
-            let target = window().unwrap().document().unwrap().get_element_by_id(&id);
-            if let Some(_el) = target {
-                //self.core.scroll_to(Some(el), None);
-            }
+        //the native scroll starts from the top
+        if !core.borrow().scroll.is_smooth() {
+            core.borrow().scroll.get_native().scroller.scroll_to(scroll.x, scroll.y);
         }
+
+        Ok(core)
     }
- 
 }
 
 
@@ -1,128 +1,693 @@
 use std::{rc::Rc, cell::RefCell};
 
-
+use js_sys::{Date, Promise};
 use wasm_bindgen::{prelude::Closure, JsCast};
-use web_sys::{Element, Window, window, HtmlElement, console, ScrollToOptions, ScrollBehavior};
+use web_sys::{Element, window, HtmlElement, ScrollToOptions, ScrollBehavior, Event, EventTarget, WheelEvent, AddEventListenerOptions};
 
-use crate::{option::LocomotiveOption, virtual_scroll::VirtualScroll, utils::{els::{ScrollToOption, ScrollToTarget}, instance::Instance}};
+use crate::{error::LocomotiveError, core::Core, engine::{self, Rect}, smooth::get_gesture_direction, virtual_scroll::LINE_HEIGHT, option::{LocomotiveOption, Position, GestureDirection}, utils::{listeners::ListenerEvent, els::{MappedEl, ScrollToOption, ScrollToTarget, ScrollToPromise, ScrollToInterrupt}, instance::Instance, get_target_el}};
 
 
+/// How long the window has to stay still before `scrollEnd` is emitted.
+const SCROLL_END_DELAY: i32 = 100;
 
 
 #[derive(Debug, Clone)]
 pub struct NativeScroll {
-    pub options: LocomotiveOption,
-    pub virtual_scroll: Option<VirtualScroll>,
+    /// Shared with the core, `setOptions` changes are seen by every closure.
+    pub options: Rc<RefCell<LocomotiveOption>>,
+    /// The window, or the container when it scrolls on its own.
+    pub scroller: Scroller,
+    pub is_scrolling: Rc<RefCell<bool>>,
+    pub stop: Rc<RefCell<bool>>,
+    pub speed_ts: Rc<RefCell<Option<f64>>>,
+
+    //checkScroll
+    pub check_scroll: Rc<RefCell<Option<Closure<dyn Fn()>>>>,
+    pub detect_elements_cb: Rc<RefCell<Option<Closure<dyn Fn()>>>>,
+    pub detect_elements_raf: Rc<RefCell<Option<i32>>>,
+    pub scroll_end_cb: Rc<RefCell<Option<Closure<dyn Fn()>>>>,
+    pub scroll_end_timeout: Rc<RefCell<Option<i32>>>,
+
+    //horizontal
+    pub wheel_cb: Rc<RefCell<Option<Closure<dyn Fn(WheelEvent)>>>>,
+
+    //scrollTo
+    pub scroll_to_listener: Rc<RefCell<Option<Closure<dyn Fn(Event)>>>>,
+    pub scroll_to_promise: Rc<RefCell<Option<ScrollToPromise>>>,
 }
 
 impl NativeScroll {
-    pub fn _new(options: LocomotiveOption, window: &Window, check_scroll_cb: Closure<dyn Fn()>) -> Self {
-        
+    pub fn new(options: Rc<RefCell<LocomotiveOption>>, core: Rc<RefCell<Core>>) -> Self {
+        let window = window().unwrap();
+        let scroller = Scroller::of(&options.borrow());
+
         //1
-        if options.reset_native_scroll {
+        if options.borrow().reset_native_scroll {
             let history = window.history().unwrap();
             if history.scroll_restoration().is_ok() {
                 history.set_scroll_restoration(web_sys::ScrollRestoration::Manual).unwrap();
             }
-            window.scroll_to_with_x_and_y(0.0, 0.0);
+            scroller.scroll_to(0.0, 0.0);
+        }
+
+        let mut scroll = Self {
+            options,
+            scroller,
+            is_scrolling: Rc::new(RefCell::new(false)),
+            stop: Rc::new(RefCell::new(false)),
+            speed_ts: Rc::new(RefCell::new(None)),
+
+            check_scroll: Rc::new(RefCell::new(None)),
+            detect_elements_cb: Rc::new(RefCell::new(None)),
+            detect_elements_raf: Rc::new(RefCell::new(None)),
+            scroll_end_cb: Rc::new(RefCell::new(None)),
+            scroll_end_timeout: Rc::new(RefCell::new(None)),
+
+            wheel_cb: Rc::new(RefCell::new(None)),
+
+            scroll_to_listener: Rc::new(RefCell::new(None)),
+            scroll_to_promise: Rc::new(RefCell::new(None)),
+        };
+
+        scroll.check_scroll_cb(core.clone());
+        scroll.detect_elements_cb(core.clone());
+        scroll.scroll_end_cb(core.clone());
+        scroll.wheel_cb(core);
+
+        scroll
+    }
+
+    pub fn init(core: Rc<RefCell<Core>>, options: &LocomotiveOption) -> Result<(), LocomotiveError> {
+        NativeScroll::set_viewport(&mut core.borrow_mut());
+        {
+            let core_ref = core.borrow();
+            let mut instance = core_ref.instance.borrow_mut();
+            let page_offset = core_ref.scroll.get_native().scroller.offset();
+            instance.set_scroll(page_offset.clone());
+            instance.set_delta(page_offset);
         }
 
-        //2
-        window.add_event_listener_with_callback_and_bool("scroll", check_scroll_cb.as_ref().unchecked_ref(), false).unwrap();
-        check_scroll_cb.forget();
-        //3
-        /*
-        if (window.smoothscrollPolyfill === undefined) {
-            window.smoothscrollPolyfill = smoothscroll;
-            window.smoothscrollPolyfill.polyfill();
+        NativeScroll::set_scroll_limit(&core.borrow());
+        NativeScroll::add_elements(core.clone(), options)?;
+        Core::detect_elements(None, core.clone(), options);
+
+        let core_ref = core.borrow();
+        let scroll = core_ref.scroll.get_native();
+        let target = scroll.scroller.target();
+        target.add_event_listener_with_callback("scroll", scroll.check_scroll.borrow().as_ref().unwrap().as_ref().unchecked_ref()).unwrap();
+
+        //the wheel has to be turned into horizontal scroll by hand, so the listener can't be passive
+        if *core_ref.direction_axis.borrow() == 'x' {
+            let listener_options = AddEventListenerOptions::new();
+            listener_options.set_passive(false);
+            target.add_event_listener_with_callback_and_add_event_listener_options("wheel", scroll.wheel_cb.borrow().as_ref().unwrap().as_ref().unchecked_ref(), &listener_options).unwrap();
         }
-        */
 
-        Self {
-            options,
-            virtual_scroll: None
+        Ok(())
+    }
+
+    /// Elements are detected against the scroller's own viewport.
+    fn set_viewport(core: &mut Core) {
+        let size = core.scroll.get_native().scroller.size();
+        core.window_width = size.x;
+        core.window_height = size.y;
+        core.window_middle = Position {
+            x: core.window_width / 2.0,
+            y: core.window_height / 2.0,
+        };
+    }
+
+    fn set_scroll_limit(core: &Core) {
+        let scroll_size = core.scroll.get_native().scroller.scroll_size(&core.html.borrow());
+        core.instance.borrow_mut().limit = Position::new(
+            f64::max(0.0, scroll_size.x - core.window_width),
+            f64::max(0.0, scroll_size.y - core.window_height),
+        );
+    }
+
+    fn add_elements(core: Rc<RefCell<Core>>, options: &LocomotiveOption) -> Result<(), LocomotiveError> {
+        let (els, scroll_pos, window_size, origin, direction_axis) = {
+            let core_ref = core.borrow();
+            let scroll_pos = core_ref.instance.borrow().scroll.clone();
+            let window_size = Position::new(core_ref.window_width, core_ref.window_height);
+            let origin = core_ref.scroll.get_native().scroller.origin();
+            let direction_axis = *core_ref.direction_axis.borrow();
+            (core_ref.els.clone(), scroll_pos, window_size, origin, direction_axis)
+        };
+        {
+            els.borrow_mut().data.clear();
+        }
+
+        let names = options.names.as_ref().unwrap();
+        let nodes = options.query_own(&names.data);
+
+        for (index, el) in nodes.into_iter().enumerate() {
+            let dataset = el.dyn_ref::<HtmlElement>().unwrap().dataset();
+
+            let cl = dataset.get(&names.class).unwrap_or(options.class.clone());
+            let id = dataset.get(&names.id).unwrap_or(format!("el{}", index));
+            let repeat = match dataset.get(&names.repeat) {
+                Some(val) => val.as_str().trim() != "false",
+                None => options.repeat
+            };
+            let call = dataset.get(&names.call);
+            let offset = match dataset.get(&names.offset) {
+                Some(val) => val.split(",").map(|s| s.trim().to_string()).collect::<Vec<String>>(),
+                None => vec![options.offset[0].to_string(), options.offset[1].to_string()]
+            };
+            let target_el = get_target_el(&el, dataset.get(&names.target), &names.target)?;
+
+            let rect = NativeScroll::get_bounds(&target_el, &offset, &scroll_pos, &origin, &window_size, direction_axis);
+
+            let mapped_el = MappedEl {
+                el: Some(el.clone()),
+                target_el: Some(target_el.clone()),
+                id: id.clone(),
+                class: cl.clone(),
+                top: rect.top,
+                bottom: rect.bottom,
+                middle: Some(rect.middle()),
+                left: rect.left,
+                right: rect.right,
+                offset,
+                progress: Some(0.0),
+                repeat: Some(repeat),
+                in_view: Some(false),
+                call,
+                ..Default::default()
+            };
+            let mapped_el = Rc::new(RefCell::new(mapped_el));
+            {
+                els.borrow_mut().data.insert(id.clone(), mapped_el.clone());
+            }
+            if el.class_list().contains(&cl) {
+                Core::set_in_view(&mut mapped_el.borrow_mut(), &id, core.clone(), options, false);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Re-measure the elements in place, their in-view state is kept.
+    fn update_elements(core: &Core) {
+        let scroll_pos = core.instance.borrow().scroll.clone();
+        let window_size = Position::new(core.window_width, core.window_height);
+        let origin = core.scroll.get_native().scroller.origin();
+        let direction_axis = *core.direction_axis.borrow();
+
+        for el in core.els.borrow().data.values() {
+            let mut el = el.borrow_mut();
+            let target_el = el.target_el.clone().unwrap();
+            let Rect { top, bottom, left, right } = NativeScroll::get_bounds(&target_el, &el.offset, &scroll_pos, &origin, &window_size, direction_axis);
+
+            el.top = top;
+            el.bottom = bottom;
+            el.left = left;
+            el.right = right;
         }
 
+        *core.has_scroll_ticking.borrow_mut() = false;
+    }
+
+    /// Bounds of `target_el` in the scroller, narrowed by `offset` on the scroll axis.
+    fn get_bounds(target_el: &Element, offset: &[String], scroll_pos: &Position, origin: &Position, window_size: &Position, direction_axis: char) -> Rect {
+        let target_el_bcr = target_el.get_bounding_client_rect();
+        let target_el_html = target_el.dyn_ref::<HtmlElement>().unwrap();
+        let top = target_el_bcr.top() - origin.y + scroll_pos.y;
+        let left = target_el_bcr.left() - origin.x + scroll_pos.x;
+        let bottom = top + target_el_html.offset_height() as f64;
+        let right = left + target_el_html.offset_width() as f64;
+
+        let relative_offset = engine::relative_offset(offset, window_size.get(direction_axis));
+        Rect::new(top, bottom, left, right).with_offset(relative_offset, direction_axis)
     }
 
-    pub fn _scroll_to(target: ScrollToTarget, option: ScrollToOption, html: &Element, instance: Rc<RefCell<Instance>>) {
-        let mut offset = match option.offset {
-            Some(val) => val.parse::<f64>().unwrap().trunc(),
+    pub fn check_scroll(core: Rc<RefCell<Core>>, options: &LocomotiveOption) {
+        let window = window().unwrap();
+        let (scroll, instance, html, has_scroll_ticking, has_els, direction_axis) = {
+            let core_ref = core.borrow();
+            let has_els = !core_ref.els.borrow().data.is_empty();
+            let direction_axis = *core_ref.direction_axis.borrow();
+            (core_ref.scroll.get_native().clone(), core_ref.instance.clone(), core_ref.html.clone(), core_ref.has_scroll_ticking.clone(), has_els, direction_axis)
+        };
+
+        if !*scroll.is_scrolling.borrow() {
+            {
+                *scroll.is_scrolling.borrow_mut() = true;
+            }
+            let _ = html.borrow().class_list().add_1(&options.scrolling_class);
+            Core::emit(core.clone(), ListenerEvent::ScrollStart);
+        }
+
+        //`scrollEnd` fires once the window stopped moving for a while
+        if let Some(handle) = scroll.scroll_end_timeout.borrow_mut().take() {
+            window.clear_timeout_with_handle(handle);
+        }
+        if let Some(cb) = scroll.scroll_end_cb.borrow().as_ref() {
+            *scroll.scroll_end_timeout.borrow_mut() = window.set_timeout_with_callback_and_timeout_and_arguments_0(cb.as_ref().unchecked_ref(), SCROLL_END_DELAY).ok();
+        }
+
+        {
+            let page_offset = scroll.scroller.offset();
+            let mut instance = instance.borrow_mut();
+
+            if options.get_direction {
+                NativeScroll::add_direction(&mut instance, &page_offset, direction_axis);
+            }
+            if options.get_speed {
+                NativeScroll::add_speed(&mut instance, &page_offset, scroll.speed_ts.clone(), direction_axis);
+            }
+
+            instance.set_scroll(page_offset.clone());
+            instance.set_delta(page_offset);
+        }
+
+        if has_els && !*has_scroll_ticking.borrow() {
+            {
+                *has_scroll_ticking.borrow_mut() = true;
+            }
+            if let Some(cb) = scroll.detect_elements_cb.borrow().as_ref() {
+                *scroll.detect_elements_raf.borrow_mut() = window.request_animation_frame(cb.as_ref().unchecked_ref()).ok();
+            }
+        }
+
+        Core::dispatch_scroll(core, options);
+    }
+
+    fn add_direction(instance: &mut Instance, page_offset: &Position, direction_axis: char) {
+        if let Some(direction) = engine::direction(instance.scroll.get(direction_axis), page_offset.get(direction_axis), direction_axis) {
+            instance.direction = Some(direction.to_string());
+        }
+    }
+
+    fn add_speed(instance: &mut Instance, page_offset: &Position, speed_ts: Rc<RefCell<Option<f64>>>, direction_axis: char) {
+        let now = Date::now();
+        let (current, previous) = (page_offset.get(direction_axis), instance.scroll.get(direction_axis));
+
+        let elapsed = now - speed_ts.borrow().unwrap_or(0.0);
+        instance.speed = Some(engine::speed(previous, current, elapsed));
+
+        *speed_ts.borrow_mut() = Some(now);
+    }
+
+    pub fn resize(core: &mut Core) {
+        NativeScroll::set_viewport(core);
+        NativeScroll::set_scroll_limit(core);
+        NativeScroll::update_elements(core);
+    }
+
+    pub fn update(core: Rc<RefCell<Core>>, options: &LocomotiveOption) -> Result<(), LocomotiveError> {
+        NativeScroll::set_scroll_limit(&core.borrow());
+        NativeScroll::add_elements(core.clone(), options)?;
+        Core::detect_elements(None, core, options);
+        Ok(())
+    }
+
+    pub fn scroll_to(target: ScrollToTarget, scroll_to_option: ScrollToOption, core: Rc<RefCell<Core>>) -> Promise {
+        let (scroll, scroll_pos, limit, direction_axis) = {
+            let core_ref = core.borrow();
+            let instance = core_ref.instance.borrow();
+            let direction_axis = *core_ref.direction_axis.borrow();
+            (core_ref.scroll.get_native().clone(), instance.scroll.get(direction_axis), instance.limit.get(direction_axis), direction_axis)
+        };
+        let origin = scroll.scroller.origin().get(direction_axis);
+        let el_start = |el: &Element| {
+            let el_bcr = el.get_bounding_client_rect();
+            let start = match direction_axis {
+                'x' => el_bcr.left(),
+                _ => el_bcr.top()
+            };
+            start - origin
+        };
+
+        if *scroll.stop.borrow() {
+            return ScrollToPromise::rejected(ScrollToInterrupt::Stopped);
+        }
+
+        let offset = match scroll_to_option.offset.as_ref() {
+            Some(val) => val.parse::<f64>().unwrap_or(0.0).trunc(),
             None => 0.0
-        };  
+        };
 
         let target = match target {
             ScrollToTarget::String(string) => {
                 match string.as_str() {
-                    "top" => {
-                        html.get_bounding_client_rect().top() + offset + instance.as_ref().borrow().scroll.y
-                    },
-                    "bottom" => {
-                        let html_el = html.dyn_ref::<HtmlElement>().unwrap();
-                        html_el.offset_height() as f64 - window().unwrap().inner_height().unwrap().as_f64().unwrap() + offset
-                    },
-                    any => {
-                        let el = window().unwrap().document().unwrap().query_selector(any).unwrap();
-                        if let Some(el) = el {
-                            el.get_bounding_client_rect().top() + offset + instance.as_ref().borrow().scroll.y
-                        } else {
-                            console::warn_1(&"[target] parameter is not valid".into());
-                            panic!()
+                    "top" | "left" => 0.0,
+                    "bottom" | "right" => limit,
+                    selector => {
+                        match window().unwrap().document().unwrap().query_selector(selector) {
+                            Ok(Some(el)) => el_start(&el) + scroll_pos,
+                            _ => return ScrollToPromise::rejected(ScrollToInterrupt::InvalidTarget)
                         }
                     }
                 }
             },
-            ScrollToTarget::Element(el) => {
-                el.get_bounding_client_rect().top() + offset + instance.as_ref().borrow().scroll.y
-            },
-            ScrollToTarget::Num(num) => {
-                num.trunc() + offset
+            ScrollToTarget::Element(el) => el_start(&el) + scroll_pos,
+            ScrollToTarget::Num(num) => num.trunc(),
+        };
+        //the browser clamps the scroll, the target has to be reachable for the promise to settle
+        let target = engine::clamp(target + offset, limit);
+
+        NativeScroll::interrupt_scroll_to(&scroll, ScrollToInterrupt::Cancelled);
+
+        let (promise, settle) = ScrollToPromise::new();
+        let callback = scroll_to_option.callback.clone();
+        let scroller = scroll.scroller.clone();
+        let scroll_to_listener = scroll.scroll_to_listener.clone();
+        let scroll_to_promise = scroll.scroll_to_promise.clone();
+        let on_done = move || {
+            NativeScroll::remove_scroll_to_listener(&scroller, &scroll_to_listener);
+            let settle = scroll_to_promise.borrow_mut().take();
+            if let Some(settle) = settle {
+                if let Some(callback) = callback.as_ref() {
+                    callback();
+                }
+                settle.resolve(&scroller.offset());
             }
         };
 
-        offset = target;
+        if is_target_reached(&scroll.scroller, target, direction_axis) {
+            {
+                *scroll.scroll_to_promise.borrow_mut() = Some(settle);
+            }
+            on_done();
+            return promise;
+        }
 
-        if let Some(callback) = option.callback {
-            if _is_target_reached(offset) {
-                callback();
-                return;
-            } else {
-                let onscroll: Rc<RefCell<Option<Closure<dyn FnMut() >>>> = Rc::new(RefCell::new(None));
-                let onscroll2 = onscroll.clone();
-                
-                *onscroll.borrow_mut() = Some(Closure::new(move || {
-                    let callback = callback.clone();
-                    if _is_target_reached(offset) {
-                        let cb = onscroll2.borrow();
-                        let cb = cb.as_ref().unwrap().as_ref().unchecked_ref();
-                        window().unwrap().remove_event_listener_with_callback("scroll", cb).unwrap();
-
-                        callback.as_ref()();
+        let scroll2 = scroll.clone();
+        let scroller = scroll.scroller.clone();
+        let listener: Closure<dyn Fn(Event)> = Closure::new(move |event: Event| {
+            match event.type_().as_str() {
+                "scroll" => {
+                    if is_target_reached(&scroller, target, direction_axis) {
+                        on_done();
                     }
-                }));
+                },
+                //the user took over, the browser drops the smooth scroll
+                _ => NativeScroll::interrupt_scroll_to(&scroll2, ScrollToInterrupt::Wheel),
+            }
+        });
 
-                let cb = onscroll.borrow();
-                let cb = cb.as_ref().unwrap().as_ref().unchecked_ref();
-                window().unwrap().add_event_listener_with_callback("scroll", cb).unwrap();
+        let scroll_target = scroll.scroller.target();
+        for event in SCROLL_TO_EVENTS {
+            scroll_target.add_event_listener_with_callback(event, listener.as_ref().unchecked_ref()).unwrap();
+        }
+        {
+            *scroll.scroll_to_listener.borrow_mut() = Some(listener);
+            *scroll.scroll_to_promise.borrow_mut() = Some(settle);
+        }
+
+        let scroll_behavior = if scroll_to_option.duration == Some(0.0) {
+            ScrollBehavior::Instant
+        } else {
+            ScrollBehavior::Smooth
+        };
+        let scroll_to_options = ScrollToOptions::new();
+        match direction_axis {
+            'x' => scroll_to_options.set_left(target),
+            _ => scroll_to_options.set_top(target)
+        }
+        scroll_to_options.set_behavior(scroll_behavior);
+        scroll.scroller.scroll_to_with_options(&scroll_to_options);
 
+        promise
+    }
+
+    fn remove_scroll_to_listener(scroller: &Scroller, scroll_to_listener: &Rc<RefCell<Option<Closure<dyn Fn(Event)>>>>) {
+        let listener = scroll_to_listener.borrow_mut().take();
+        if let Some(listener) = listener {
+            let target = scroller.target();
+            for event in SCROLL_TO_EVENTS {
+                let _ = target.remove_event_listener_with_callback(event, listener.as_ref().unchecked_ref());
             }
         }
+    }
 
-        let scroll_to_behaviour = match option.duration {
-            Some(val) => if val == 0.0 {ScrollBehavior::Auto} else {ScrollBehavior::Smooth},
-            None => ScrollBehavior::Auto
-        };
-        let mut scroll_to_options = ScrollToOptions::new();
-        scroll_to_options.top(offset);
-        scroll_to_options.behavior(scroll_to_behaviour);
-        window().unwrap().scroll_to_with_scroll_to_options(&scroll_to_options);
+    /// Drop a pending `scrollTo` and reject its promise with `reason`.
+    pub fn interrupt_scroll_to(scroll: &NativeScroll, reason: ScrollToInterrupt) {
+        NativeScroll::remove_scroll_to_listener(&scroll.scroller, &scroll.scroll_to_listener);
+
+        let settle = scroll.scroll_to_promise.borrow_mut().take();
+        if let Some(settle) = settle {
+            settle.reject(reason);
+        }
+    }
+
+    /// Lock the page where it is, a pending `scrollTo` is rejected.
+    pub fn stop_scroll(core: Rc<RefCell<Core>>) {
+        let core_ref = core.borrow();
+        let scroll = core_ref.scroll.get_native();
+
+        {
+            *scroll.stop.borrow_mut() = true;
+        }
+        NativeScroll::interrupt_scroll_to(scroll, ScrollToInterrupt::Stopped);
+
+        let _ = scroll.scroller.overflow_el(&core_ref.html.borrow()).style().set_property("overflow", "hidden");
+    }
+
+    pub fn start_scroll(core: Rc<RefCell<Core>>) {
+        let core_ref = core.borrow();
+        let scroll = core_ref.scroll.get_native();
+
+        {
+            *scroll.stop.borrow_mut() = false;
+        }
+
+        let _ = scroll.scroller.overflow_el(&core_ref.html.borrow()).style().remove_property("overflow");
+    }
+
+    /// Remove the window listeners and pending timers, then drop the closures holding the core.
+    pub fn destroy(core: Rc<RefCell<Core>>) {
+        let core_ref = core.borrow();
+        let scroll = core_ref.scroll.get_native();
+        let window = window().unwrap();
+
+        NativeScroll::interrupt_scroll_to(scroll, ScrollToInterrupt::Destroyed);
+
+        let target = scroll.scroller.target();
+        if let Some(cb) = scroll.check_scroll.borrow().as_ref() {
+            let _ = target.remove_event_listener_with_callback("scroll", cb.as_ref().unchecked_ref());
+        }
+        if let Some(cb) = scroll.wheel_cb.borrow().as_ref() {
+            let _ = target.remove_event_listener_with_callback("wheel", cb.as_ref().unchecked_ref());
+        }
+        if let Some(handler) = scroll.detect_elements_raf.borrow_mut().take() {
+            let _ = window.cancel_animation_frame(handler);
+        }
+        if let Some(handle) = scroll.scroll_end_timeout.borrow_mut().take() {
+            window.clear_timeout_with_handle(handle);
+        }
+        if *scroll.stop.borrow() {
+            let _ = scroll.scroller.overflow_el(&core_ref.html.borrow()).style().remove_property("overflow");
+        }
+
+        //closures
+        scroll.check_scroll.borrow_mut().take();
+        scroll.detect_elements_cb.borrow_mut().take();
+        scroll.scroll_end_cb.borrow_mut().take();
+        scroll.wheel_cb.borrow_mut().take();
+    }
+}
+
+
+//CLOSURES
+impl NativeScroll {
+    fn check_scroll_cb(&mut self, core: Rc<RefCell<Core>>) {
+        let callback: Rc<RefCell<Option<Closure<dyn Fn()>>>> = Rc::new(RefCell::new(None));
+        let options = self.options.clone();
+
+        *callback.borrow_mut() = Some(Closure::new(move || {
+            let options = options.borrow().clone();
+            NativeScroll::check_scroll(core.clone(), &options);
+        }));
+
+        self.check_scroll = callback;
+    }
+
+    fn detect_elements_cb(&mut self, core: Rc<RefCell<Core>>) {
+        let callback: Rc<RefCell<Option<Closure<dyn Fn()>>>> = Rc::new(RefCell::new(None));
+        let detect_elements_raf = self.detect_elements_raf.clone();
+        let options = self.options.clone();
+
+        *callback.borrow_mut() = Some(Closure::new(move || {
+            {
+                *detect_elements_raf.borrow_mut() = None;
+            }
+            let options = options.borrow().clone();
+            Core::detect_elements(None, core.clone(), &options);
+        }));
+
+        self.detect_elements_cb = callback;
+    }
+
+    fn scroll_end_cb(&mut self, core: Rc<RefCell<Core>>) {
+        let callback: Rc<RefCell<Option<Closure<dyn Fn()>>>> = Rc::new(RefCell::new(None));
+        let is_scrolling = self.is_scrolling.clone();
+        let scroll_end_timeout = self.scroll_end_timeout.clone();
+        let options = self.options.clone();
+
+        *callback.borrow_mut() = Some(Closure::new(move || {
+            {
+                *scroll_end_timeout.borrow_mut() = None;
+                *is_scrolling.borrow_mut() = false;
+            }
+            let scrolling_class = options.borrow().scrolling_class.clone();
+            let _ = core.borrow().html.borrow().class_list().remove_1(&scrolling_class);
+            Core::emit(core.clone(), ListenerEvent::ScrollEnd);
+        }));
+
+        self.scroll_end_cb = callback;
+    }
+
+    /// Horizontal pages only: turn the wheel into horizontal scroll, according to `gesture_direction`.
+    fn wheel_cb(&mut self, core: Rc<RefCell<Core>>) {
+        let callback: Rc<RefCell<Option<Closure<dyn Fn(WheelEvent)>>>> = Rc::new(RefCell::new(None));
+        let stop = self.stop.clone();
+        let scroller = self.scroller.clone();
+        let options = self.options.clone();
+
+        *callback.borrow_mut() = Some(Closure::new(move |event: WheelEvent| {
+            if *stop.borrow() {
+                return;
+            }
+
+            let options = options.borrow().clone();
+            let delta = match get_gesture_direction(core.clone(), &options) {
+                GestureDirection::Vertical => event.delta_y(),
+                GestureDirection::Both => event.delta_x() + event.delta_y(),
+                //horizontal gestures already scroll the page on their own
+                GestureDirection::Horizontal => return
+            };
+            if delta == 0.0 {
+                return;
+            }
+
+            let delta = match event.delta_mode() {
+                WheelEvent::DOM_DELTA_LINE => delta * LINE_HEIGHT,
+                WheelEvent::DOM_DELTA_PAGE => delta * scroller.size().x,
+                _ => delta
+            };
+
+            event.prevent_default();
+            scroller.scroll_by(delta * options.multiplier, 0.0);
+        }));
 
+        self.wheel_cb = callback;
     }
+}
+
+
+/// Events a pending `scrollTo` listens to: `scroll` to settle it, the others to notice the user taking over.
+const SCROLL_TO_EVENTS: [&str; 3] = ["scroll", "wheel", "touchstart"];
+
+fn is_target_reached(scroller: &Scroller, target: f64, direction_axis: char) -> bool {
+    (scroller.offset().get(direction_axis) - target).abs() < 1.0
+}
+
 
+/// What a native instance scrolls.
+#[derive(Debug, Clone)]
+pub enum Scroller {
+    Window,
+    /// The container overflows on its own, `scroll_left`/`scroll_top` move instead of the window.
+    Element(HtmlElement),
 }
 
+impl Scroller {
+    /// A container scrolls itself when its `overflow` on the scroll axis lets it, so every instance listens to its own.
+    fn of(options: &LocomotiveOption) -> Scroller {
+        let el = match options.el.try_get_element().and_then(|el| el.dyn_ref::<HtmlElement>()) {
+            Some(el) => el.clone(),
+            None => return Scroller::Window
+        };
+        let property = match options.direction.axis() {
+            'x' => "overflow-x",
+            _ => "overflow-y"
+        };
+        let overflow = window().unwrap().get_computed_style(&el).ok().flatten()
+            .and_then(|style| style.get_property_value(property).ok())
+            .unwrap_or_default();
+
+        match overflow.as_str() {
+            "auto" | "scroll" | "overlay" => Scroller::Element(el),
+            _ => Scroller::Window
+        }
+    }
+
+    /// Where the `scroll` and `wheel` listeners go.
+    fn target(&self) -> EventTarget {
+        match self {
+            Scroller::Window => window().unwrap().into(),
+            Scroller::Element(el) => el.clone().into()
+        }
+    }
 
+    fn offset(&self) -> Position {
+        match self {
+            Scroller::Window => {
+                let window = window().unwrap();
+                Position::new(window.page_x_offset().unwrap(), window.page_y_offset().unwrap())
+            },
+            Scroller::Element(el) => Position::new(el.scroll_left() as f64, el.scroll_top() as f64)
+        }
+    }
 
-fn _is_target_reached(offset: f64) -> bool {
-    window().unwrap().page_y_offset().unwrap().trunc() == offset.trunc()
-}
\ No newline at end of file
+    fn size(&self) -> Position {
+        match self {
+            Scroller::Window => {
+                let window = window().unwrap();
+                Position::new(window.inner_width().unwrap().as_f64().unwrap(), window.inner_height().unwrap().as_f64().unwrap())
+            },
+            Scroller::Element(el) => Position::new(el.client_width() as f64, el.client_height() as f64)
+        }
+    }
+
+    fn scroll_size(&self, html: &Element) -> Position {
+        let el = match self {
+            Scroller::Window => html,
+            Scroller::Element(el) => el
+        };
+        Position::new(el.scroll_width() as f64, el.scroll_height() as f64)
+    }
+
+    /// Top left corner of the scroller's viewport in the window.
+    fn origin(&self) -> Position {
+        match self {
+            Scroller::Window => Position::new(0.0, 0.0),
+            Scroller::Element(el) => {
+                let bcr = el.get_bounding_client_rect();
+                Position::new(bcr.left() + el.client_left() as f64, bcr.top() + el.client_top() as f64)
+            }
+        }
+    }
+
+    /// Gets `overflow: hidden` while the scroll is stopped.
+    fn overflow_el(&self, html: &Element) -> HtmlElement {
+        match self {
+            Scroller::Window => html.dyn_ref::<HtmlElement>().unwrap().clone(),
+            Scroller::Element(el) => el.clone()
+        }
+    }
+
+    pub(crate) fn scroll_to(&self, x: f64, y: f64) {
+        match self {
+            Scroller::Window => window().unwrap().scroll_to_with_x_and_y(x, y),
+            Scroller::Element(el) => el.scroll_to_with_x_and_y(x, y)
+        }
+    }
+
+    fn scroll_to_with_options(&self, options: &ScrollToOptions) {
+        match self {
+            Scroller::Window => window().unwrap().scroll_to_with_scroll_to_options(options),
+            Scroller::Element(el) => el.scroll_to_with_scroll_to_options(options)
+        }
+    }
+
+    fn scroll_by(&self, x: f64, y: f64) {
+        match self {
+            Scroller::Window => window().unwrap().scroll_by_with_x_and_y(x, y),
+            Scroller::Element(el) => el.scroll_by_with_x_and_y(x, y)
+        }
+    }
+}
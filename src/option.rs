@@ -1,11 +1,13 @@
 use convert_js::ToJs;
 use serde::{Serialize, Deserialize};
-use web_sys::window;
+use js_sys::{Reflect, Object, Function, RegExp};
+use wasm_bindgen::{JsValue, JsCast};
+use web_sys::{window, console, Element};
 
-use crate::utils::element_type::ElementType;
+use crate::{utils::element_type::ElementType, bezier_easing::Easing, error::{LocomotiveError, UnknownValue}};
 
 
-#[derive(Clone, Debug, Serialize, Deserialize, ToJs)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToJs)]
 #[serde(default)]
 #[serde(rename_all = "camelCase")]
 pub struct Position {
@@ -41,19 +43,184 @@ impl Position {
     }
 }
 
+/// Options and attributes taking one of a few strings.
+pub trait OptionValue: Copy + PartialEq + 'static {
+    /// Every accepted string and the value it stands for.
+    const VALUES: &'static [(&'static str, Self)];
+
+    fn parse(value: &str) -> Result<Self, UnknownValue> {
+        Self::VALUES.iter()
+            .find(|(name, _)| *name == value)
+            .map(|(_, val)| *val)
+            .ok_or_else(|| UnknownValue { value: value.to_string(), allowed: Self::VALUES.iter().map(|(name, _)| *name).collect() })
+    }
+
+    fn as_str(&self) -> &'static str {
+        Self::VALUES.iter().find(|(_, val)| val == self).map(|(name, _)| *name).unwrap_or_default()
+    }
+}
+
+/// `direction` of the page, or `data-scroll-direction` of a parallax element.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String")]
+#[serde(rename_all = "camelCase")]
+pub enum Direction {
+    #[default]
+    Vertical,
+    Horizontal,
+}
+
+impl Direction {
+    pub fn axis(&self) -> char {
+        match self {
+            Direction::Vertical => 'y',
+            Direction::Horizontal => 'x',
+        }
+    }
+}
+
+impl OptionValue for Direction {
+    const VALUES: &'static [(&'static str, Self)] = &[("vertical", Direction::Vertical), ("horizontal", Direction::Horizontal)];
+}
+
+/// Which wheel and touch deltas scroll the page.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String")]
+#[serde(rename_all = "camelCase")]
+pub enum GestureDirection {
+    #[default]
+    Vertical,
+    Horizontal,
+    Both,
+}
+
+impl OptionValue for GestureDirection {
+    const VALUES: &'static [(&'static str, Self)] = &[
+        ("vertical", GestureDirection::Vertical),
+        ("horizontal", GestureDirection::Horizontal),
+        ("both", GestureDirection::Both),
+    ];
+}
+
+/// `data-scroll-position`, what a parallax element moves relative to. The middle of the viewport when not set.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParallaxPosition {
+    Top,
+    Bottom,
+    ElementTop,
+    Left,
+    Right,
+    ElementLeft,
+}
+
+impl OptionValue for ParallaxPosition {
+    const VALUES: &'static [(&'static str, Self)] = &[
+        ("top", ParallaxPosition::Top),
+        ("bottom", ParallaxPosition::Bottom),
+        ("elementTop", ParallaxPosition::ElementTop),
+        ("left", ParallaxPosition::Left),
+        ("right", ParallaxPosition::Right),
+        ("elementLeft", ParallaxPosition::ElementLeft),
+    ];
+}
+
+/// The device the page is shown on, each one with its own `smooth` and `direction`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Context {
+    #[default]
+    Desktop,
+    Tablet,
+    Smartphone,
+}
+
+impl OptionValue for Context {
+    const VALUES: &'static [(&'static str, Self)] = &[
+        ("desktop", Context::Desktop),
+        ("tablet", Context::Tablet),
+        ("smartphone", Context::Smartphone),
+    ];
+}
+
+/// What the media queries tell about the device.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MediaState {
+    /// `(pointer: coarse) and (hover: none)`, a touch screen is the main input.
+    pub touch: bool,
+    /// At least `tablet.breakpoint` wide.
+    pub tablet_width: bool,
+    /// At least `smartphone.breakpoint` wide.
+    pub desktop_width: bool,
+}
+
+impl Context {
+    /// A touch device is a tablet from `tablet.breakpoint` on and a smartphone below it,
+    /// any other device is a smartphone below `smartphone.breakpoint`.
+    pub fn from_media(media: MediaState) -> Context {
+        match (media.touch, media.tablet_width, media.desktop_width) {
+            (true, true, _) => Context::Tablet,
+            (true, false, _) => Context::Smartphone,
+            (false, _, true) => Context::Desktop,
+            (false, _, false) => Context::Smartphone,
+        }
+    }
+}
+
+/// `None` where `matchMedia` is not available.
+fn matches_media(query: &str) -> Option<bool> {
+    window().unwrap().match_media(query).ok().flatten().map(|list| list.matches())
+}
+
+/// Fallback of the `pointer` and `hover` queries on browsers without `matchMedia`.
+fn touch_user_agent() -> bool {
+    let navigator = window().unwrap().navigator();
+    let reg_exp = RegExp::new("Android|iPhone|iPad|iPod|BlackBerry|IEMobile|Opera Mini", "i");
+
+    reg_exp.test(&navigator.user_agent().unwrap_or_default()) ||
+        (navigator.platform().unwrap_or_default() == "MacIntel" && navigator.max_touch_points() > 1)
+}
+
+impl TryFrom<String> for Direction {
+    type Error = UnknownValue;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Direction::parse(&value)
+    }
+}
+
+impl TryFrom<String> for GestureDirection {
+    type Error = UnknownValue;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        GestureDirection::parse(&value)
+    }
+}
+
+impl ToJs for Direction {
+    fn to_js(&self) -> JsValue {
+        self.as_str().into()
+    }
+}
+
+impl ToJs for GestureDirection {
+    fn to_js(&self) -> JsValue {
+        self.as_str().into()
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, ToJs)]
 #[serde(default)]
 #[serde(rename_all = "camelCase")]
 pub struct Tablet {
     pub smooth: bool,
-    pub direction: String,
-    pub gesture_direction: String,
+    pub direction: Direction,
+    pub gesture_direction: GestureDirection,
+    /// From this width on a touch device is a tablet, below it a smartphone.
     pub breakpoint: f64,
 }
 
 impl Default for Tablet {
     fn default() -> Self {
-        Tablet { smooth: false, direction: "vertical".to_string(), gesture_direction: "vertical".to_string(), breakpoint: 1024. }
+        Tablet { smooth: false, direction: Direction::Vertical, gesture_direction: GestureDirection::Vertical, breakpoint: 1024. }
     }
 }
 
@@ -64,39 +231,99 @@ impl Default for Tablet {
 #[serde(rename_all = "camelCase")]
 pub struct Smartphone {
     pub smooth: bool,
-    pub direction: String,
-    pub gesture_direction: String,
+    pub direction: Direction,
+    pub gesture_direction: GestureDirection,
+    /// Below this width a device without a touch screen is handled as a smartphone too.
+    pub breakpoint: f64,
 }
 
 impl Default for Smartphone {
     fn default() -> Self {
-        Smartphone { smooth: false, direction: "vertical".to_string(), gesture_direction: "vertical".to_string() }
+        Smartphone { smooth: false, direction: Direction::Vertical, gesture_direction: GestureDirection::Vertical, breakpoint: 1024. }
+    }
+}
+
+/// How the smooth scroll catches up with the target position, `{ type: "lerp" | "spring" | "friction", ... }`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+#[serde(rename_all = "camelCase")]
+pub enum Physics {
+    /// Moves by `lerp` of the remaining distance every frame.
+    #[default]
+    Lerp,
+    Spring(Spring),
+    Friction(Friction),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+#[serde(rename_all = "camelCase")]
+pub struct Spring {
+    pub stiffness: f64,
+    /// Critically damped when not set.
+    pub damping: Option<f64>,
+    pub mass: f64,
+}
+
+impl Default for Spring {
+    fn default() -> Self {
+        Spring { stiffness: 170., damping: None, mass: 1. }
     }
 }
 
+/// The scroll follows like with `lerp`, but the target keeps moving after a flick.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+#[serde(rename_all = "camelCase")]
+pub struct Friction {
+    /// Share of the velocity kept every 1/60th of a second once the input stops.
+    pub friction: f64,
+}
+
+impl Default for Friction {
+    fn default() -> Self {
+        Friction { friction: 0.9 }
+    }
+}
+
+/// The options that differ between desktop, tablet and smartphone.
+#[derive(Clone, Copy, Debug)]
+pub struct ContextOption {
+    pub smooth: bool,
+    pub direction: Direction,
+    pub gesture_direction: GestureDirection,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default)]
 #[serde(rename_all = "camelCase")]
 pub struct LocomotiveOption {
+    /// The container, found with `query` unless an element is given.
     #[serde(skip_serializing, skip_deserializing)]
     pub el: ElementType,
     pub query: String,
     pub name: String,
+    /// Prefix of the `{namespace}scroll`, `{namespace}call` and `{namespace}contextchange` DOM events.
+    pub namespace: String,
     pub offset: [f64; 2],
     pub repeat: bool,
     pub smooth: bool,
     pub init_position: Position,
-    pub direction: String,
-    pub gesture_direction: String,
+    pub direction: Direction,
+    pub gesture_direction: GestureDirection,
+    /// Switch between smooth and native scroll in place when the context changes, e.g. when a tablet rotates.
     pub reload_on_context_change: bool,
     pub lerp: f64,
     pub class: String,
-    pub scroll_bar_container: bool,
+    /// Where the scrollbar goes, `document.body` when not set.
+    #[serde(skip_serializing, skip_deserializing)]
+    pub scroll_bar_container: Option<Element>,
     pub scroll_bar_class: String,
     pub scrolling_class: String,
     pub dragging_class: String,
     pub smooth_class: String,
     pub init_class: String,
+    pub stop_class: String,
     pub get_speed: bool,
     pub get_direction: bool,
     pub scroll_from_anywhere: bool,
@@ -108,9 +335,23 @@ pub struct LocomotiveOption {
     pub is_tablet: bool,
     pub smartphone: Option<Smartphone>,
     pub is_mobile: bool,
+    /// `contextResolver(detected, { width, touch })`, returns "desktop", "tablet" or "smartphone"
+    /// to override the detected context, or nothing to keep it.
+    #[serde(skip_serializing, skip_deserializing)]
+    pub context_resolver: Option<Function>,
 
     //SMOOTH OPTIONS
     pub inertia: Option<f64>,
+    pub physics: Physics,
+    /// Distance scrolled by the arrow keys.
+    pub key_step: f64,
+    /// Distance scrolled by page up/down and space, the window size when not set.
+    pub page_step: Option<f64>,
+    /// Space kept around an element scrolled into view when it gets the focus.
+    pub focus_margin: f64,
+    /// Default easing of `scrollTo`, read from the JS object by `Easing::from_js`.
+    #[serde(skip_serializing, skip_deserializing)]
+    pub easing: Easing,
 
     //NAMES
     pub names: Option<Names>,
@@ -122,21 +363,23 @@ impl Default for LocomotiveOption {
             el: ElementType::Document(window().unwrap().document().unwrap()),
             query: String::from("[data-scroll-container]"),
             name: "scroll".to_string(),
+            namespace: "locomotive".to_string(),
             offset: [0., 0.],
             repeat: false,
             smooth: false,
             init_position: Position::default(),
-            direction: "vertical".to_string(),
-            gesture_direction: "vertical".to_string(),
+            direction: Direction::Vertical,
+            gesture_direction: GestureDirection::Vertical,
             reload_on_context_change: false,
             lerp: 0.1,
             class: "is_inview".to_string(),
-            scroll_bar_container: false,
+            scroll_bar_container: None,
             scroll_bar_class: "c-scrollbar".to_string(),
             scrolling_class: "has-scroll-scrolling".to_string(),
             dragging_class: "has-scroll-dragging".to_string(),
             smooth_class: "has-scroll-smooth".to_string(),
             init_class: "has-scroll-init".to_string(),
+            stop_class: "has-scroll-stopped".to_string(),
             get_speed: false,
             get_direction: false,
             scroll_from_anywhere: false,
@@ -148,97 +391,247 @@ impl Default for LocomotiveOption {
             is_tablet: false,
             smartphone: Some(Smartphone::default()),
             is_mobile: false,
+            context_resolver: None,
             names: None,
 
             inertia: None,
+            physics: Physics::default(),
+            key_step: 240.,
+            page_step: None,
+            focus_margin: 40.,
+            easing: Easing::default(),
         }
     }
 }
 
 impl LocomotiveOption {
+    /// Options given to the constructor. A wrong value is reported with the path of its field, e.g. `[tablet.direction]`.
+    pub fn from_js(value: &JsValue) -> Result<Self, LocomotiveError> {
+        if value.is_undefined() || value.is_null() {
+            return Ok(LocomotiveOption::default());
+        }
 
-    /* 
-    Object.assign(this, defaults, options);
-    this.smartphone = defaults.smartphone;
-    if (options.smartphone) Object.assign(this.smartphone, options.smartphone);
-    this.tablet = defaults.tablet;
-    if (options.tablet) Object.assign(this.tablet, options.tablet);
-    */
-    pub fn _overwrite(&mut self, rhs: Self) {
-        self.el = rhs.el;
-        self.name = rhs.name;
-        self.offset = rhs.offset;
-        self.smooth = rhs.smooth;
-        self.init_position = rhs.init_position;
-        self.direction = rhs.direction;
-        self.gesture_direction = rhs.gesture_direction;
-        self.reload_on_context_change = rhs.reload_on_context_change;
-        self.lerp = rhs.lerp;
-        self.class = rhs.class;
-        self.scroll_bar_container = rhs.scroll_bar_container;
-        self.scroll_bar_class = rhs.scroll_bar_class;
-        self.scrolling_class = rhs.scrolling_class;
-        self.dragging_class = rhs.dragging_class;
-        self.smooth_class = rhs.smooth_class;
-        self.init_class = rhs.init_class;
-        self.get_speed = rhs.get_speed;
-        self.get_direction = rhs.get_direction;
-        self.scroll_from_anywhere = rhs.scroll_from_anywhere;
-        self.multiplier = rhs.multiplier;
-        self.firefox_multiplier = rhs.firefox_multiplier;
-        self.touch_multiplier = rhs.touch_multiplier;
-        self.reset_native_scroll = rhs.reset_native_scroll;
-        if self.smartphone.is_none() {
-            self.smartphone = rhs.smartphone;
+        let mut options: LocomotiveOption = serde_path_to_error::deserialize(serde_wasm_bindgen::Deserializer::from(value.clone()))
+            .map_err(|err| LocomotiveError::InvalidOptions(format!("[{}] {}", err.path(), err.inner())))?;
+        let easing = Reflect::get(value, &"easing".into()).unwrap_or(JsValue::UNDEFINED);
+        if let Some(easing) = Easing::from_js(&easing).map_err(|err| LocomotiveError::InvalidOptions(format!("[easing] {err}")))? {
+            options.easing = easing;
+        }
+        let el = Reflect::get(value, &"el".into()).unwrap_or(JsValue::UNDEFINED);
+        if !el.is_undefined() && !el.is_null() {
+            let el = el.dyn_into::<Element>().map_err(|_| LocomotiveError::InvalidOptions("[el] expected an element".to_string()))?;
+            options.el = ElementType::from_element(el);
+        }
+        let scroll_bar_container = Reflect::get(value, &"scrollBarContainer".into()).unwrap_or(JsValue::UNDEFINED);
+        if scroll_bar_container.is_truthy() {
+            let scroll_bar_container = scroll_bar_container.dyn_into::<Element>()
+                .map_err(|_| LocomotiveError::InvalidOptions("[scrollBarContainer] expected an element".to_string()))?;
+            options.scroll_bar_container = Some(scroll_bar_container);
+        }
+        let context_resolver = Reflect::get(value, &"contextResolver".into()).unwrap_or(JsValue::UNDEFINED);
+        if !context_resolver.is_undefined() && !context_resolver.is_null() {
+            let context_resolver = context_resolver.dyn_into::<Function>()
+                .map_err(|_| LocomotiveError::InvalidOptions("[contextResolver] expected a function".to_string()))?;
+            options.context_resolver = Some(context_resolver);
         }
-        if self.tablet.is_none() {
-            self.tablet = rhs.tablet;
+        if let Some(inertia) = options.inertia {
+            options.lerp = inertia * 0.1;
         }
+
+        Ok(options)
     }
 
-    pub fn check_mobile(&mut self) {
-        let navigator = window().unwrap().navigator();
-        let inner_width = window().unwrap().inner_width().unwrap().as_f64().unwrap();
-        let reg_exp = js_sys::RegExp::new("/Android|iPhone|iPad|iPod|BlackBerry|IEMobile|Opera Mini/", "i");
 
-        let is_mobile = reg_exp.test(&navigator.user_agent().unwrap()) || 
-            (navigator.platform().unwrap() == "MacIntel".to_string() && navigator.max_touch_points() > 1) || 
-            inner_width < 1024.0;
+    /// The current options with the fields of `partial` on top. `tablet` and `smartphone` are merged
+    /// one level deep, so `{ tablet: { smooth: true } }` keeps the tablet direction.
+    pub fn merge(&self, partial: &JsValue) -> Result<Self, LocomotiveError> {
+        if partial.is_undefined() || partial.is_null() {
+            return Ok(self.clone());
+        }
+        if !partial.is_object() {
+            return Err(LocomotiveError::InvalidOptions("setOptions expects an object".to_string()));
+        }
 
-        self.is_mobile = is_mobile;
-    }
+        let current = serde_wasm_bindgen::to_value(self).map_err(|err| LocomotiveError::InvalidOptions(err.to_string()))?;
+        let merged = Object::assign(&Object::new(), current.unchecked_ref());
+        //`inertia` already went into `lerp`, it is only applied again when given
+        let _ = Reflect::delete_property(&merged, &"inertia".into());
+        for key in Object::keys(partial.unchecked_ref::<Object>()).iter() {
+            let value = Reflect::get(partial, &key).unwrap_or(JsValue::UNDEFINED);
+            let previous = Reflect::get(&current, &key).unwrap_or(JsValue::UNDEFINED);
+            let value = match key.as_string().as_deref() {
+                Some("tablet" | "smartphone") if previous.is_object() && value.is_object() => {
+                    Object::assign(&Object::assign(&Object::new(), previous.unchecked_ref()), value.unchecked_ref()).into()
+                },
+                _ => value
+            };
+            let _ = Reflect::set(&merged, &key, &value);
+        }
 
-    pub fn check_tablet(&mut self) {
-        let inner_width = window().unwrap().inner_width().unwrap().as_f64().unwrap();
-        let is_tablet = self.is_mobile && inner_width >= 1024.0;
+        let mut options = LocomotiveOption::from_js(&merged)?;
+        //a new `query` is looked up again
+        let has = |key: &str| Reflect::has(partial, &key.into()).unwrap_or(false);
+        if !has("el") && !has("query") {
+            options.el = self.el.clone();
+        }
+        if !has("scrollBarContainer") {
+            options.scroll_bar_container = self.scroll_bar_container.clone();
+        }
+        options.names = self.names.clone();
+        if !has("inertia") {
+            options.inertia = self.inertia;
+        }
+        if !has("easing") {
+            options.easing = self.easing.clone();
+        }
+        if !has("contextResolver") {
+            options.context_resolver = self.context_resolver.clone();
+        }
 
-        self.is_tablet = is_tablet;
+        Ok(options)
     }
 
-    pub(crate) fn check_mobile_bool(&self) -> bool {
-        let navigator = window().unwrap().navigator();
-        let inner_width = window().unwrap().inner_width().unwrap().as_f64().unwrap();
-        let reg_exp = js_sys::RegExp::new("/Android|iPhone|iPad|iPod|BlackBerry|IEMobile|Opera Mini/", "i");
-
-        let is_mobile = reg_exp.test(&navigator.user_agent().unwrap()) || 
-            (navigator.platform().unwrap() == "MacIntel".to_string() && navigator.max_touch_points() > 1) || 
-            inner_width < 1024.0;
+    /// Whether going from `self` to `new` needs a new instance: these options are only read while building it.
+    /// Both should have their context detected, see `detect_context`.
+    pub fn needs_reinit(&self, new: &Self) -> bool {
+        let context = |tablet: &Option<Tablet>, smartphone: &Option<Smartphone>| (
+            tablet.as_ref().map(|tablet| (tablet.smooth, tablet.direction)),
+            smartphone.as_ref().map(|smartphone| (smartphone.smooth, smartphone.direction)),
+        );
+
+        self.query != new.query ||
+            self.el.try_get_element() != new.el.try_get_element() ||
+            self.name != new.name ||
+            self.namespace != new.namespace ||
+            self.smooth != new.smooth ||
+            self.direction != new.direction ||
+            self.current_context() != new.current_context() ||
+            context(&self.tablet, &self.smartphone) != context(&new.tablet, &new.smartphone) ||
+            self.scroll_bar_class != new.scroll_bar_class ||
+            self.scroll_bar_container != new.scroll_bar_container ||
+            self.smooth_class != new.smooth_class ||
+            self.init_class != new.init_class ||
+            self.get_speed != new.get_speed ||
+            self.get_direction != new.get_direction ||
+            self.scroll_from_anywhere != new.scroll_from_anywhere ||
+            self.firefox_multiplier != new.firefox_multiplier ||
+            self.touch_multiplier != new.touch_multiplier
+    }
 
-        is_mobile
+    /// Queries the width at both breakpoints and whether the main input is a touch screen.
+    pub fn media_state(&self) -> MediaState {
+        let tablet_breakpoint = self.tablet.as_ref().map_or(Tablet::default().breakpoint, |tablet| tablet.breakpoint);
+        let smartphone_breakpoint = self.smartphone.as_ref().map_or(Smartphone::default().breakpoint, |smartphone| smartphone.breakpoint);
+        let wider_than = |breakpoint: f64| matches_media(&format!("(min-width: {breakpoint}px)"))
+            .unwrap_or_else(|| window().unwrap().inner_width().unwrap().as_f64().unwrap() >= breakpoint);
+
+        MediaState {
+            touch: matches_media("(pointer: coarse) and (hover: none)").unwrap_or_else(touch_user_agent),
+            tablet_width: wider_than(tablet_breakpoint),
+            desktop_width: wider_than(smartphone_breakpoint),
+        }
     }
 
-    pub(crate) fn check_tablet_bool(&self) -> bool {
-        let inner_width = window().unwrap().inner_width().unwrap().as_f64().unwrap();
-        let is_tablet = self.is_mobile && inner_width >= 1024.0;
+    /// Detects the context, asks `contextResolver` if set, and keeps the result in `is_mobile` and `is_tablet`.
+    pub fn detect_context(&mut self) -> Result<Context, LocomotiveError> {
+        let media = self.media_state();
+        let mut context = Context::from_media(media);
+
+        if let Some(context_resolver) = &self.context_resolver {
+            let info = Object::new();
+            let _ = Reflect::set(&info, &"width".into(), &window().unwrap().inner_width().unwrap());
+            let _ = Reflect::set(&info, &"touch".into(), &media.touch.into());
+            let resolved = context_resolver.call2(&JsValue::UNDEFINED, &context.as_str().into(), &info)
+                .map_err(|err| LocomotiveError::InvalidOptions(format!("[contextResolver] threw {}", err.as_string().unwrap_or_else(|| format!("{err:?}")))))?;
+            if !resolved.is_undefined() && !resolved.is_null() {
+                let resolved = resolved.as_string().unwrap_or_else(|| format!("{resolved:?}"));
+                context = Context::parse(&resolved).map_err(|err| LocomotiveError::InvalidOptions(format!("[contextResolver] {err}")))?;
+            }
+        }
 
-        is_tablet
+        self.is_mobile = context != Context::Desktop;
+        self.is_tablet = context == Context::Tablet;
+        Ok(context)
     }
 
-    pub(crate) fn init(&mut self) {
+    pub(crate) fn init(&mut self) -> Result<(), LocomotiveError> {
         let names = Names::new(&self.name, &self.scroll_bar_class);
         self.names = Some(names);
-        let el = window().unwrap().document().unwrap().query_selector(&self.query).unwrap().unwrap();
+        if self.el.try_get_element().is_some() {
+            return Ok(());
+        }
+
+        let el = window().unwrap().document().unwrap().query_selector(&self.query)
+            .map_err(|_| LocomotiveError::InvalidOptions(format!("[query] \"{}\" is not a valid selector", self.query)))?
+            .ok_or_else(|| LocomotiveError::ContainerNotFound(self.query.clone()))?;
         self.el = ElementType::from_element(el);
+        Ok(())
+    }
+
+    /// Elements of the container matching `selectors`, without the ones of another instance nested in it.
+    pub(crate) fn query_own(&self, selectors: &str) -> Vec<Element> {
+        let container = self.el.get_element();
+        let instance = format!("[{}]", self.names.as_ref().unwrap().instance);
+        let nodes = match self.el.query_selector_all(selectors) {
+            Some(nodes) => nodes,
+            None => return vec![],
+        };
+
+        (0 .. nodes.length())
+            .filter_map(|index| nodes.get(index)?.dyn_into::<Element>().ok())
+            .filter(|el| match el.parent_element().and_then(|parent| parent.closest(&instance).ok().flatten()) {
+                Some(owner) => owner == *container,
+                None => true,
+            })
+            .collect()
+    }
+
+    /// Options of a context. `tablet` and `smartphone` can be set to `null`, which leaves that context unsupported.
+    pub(crate) fn context_option(&self, context: Context) -> Result<ContextOption, LocomotiveError> {
+        let (smooth, direction, gesture_direction) = match context {
+            Context::Desktop => (self.smooth, self.direction, self.gesture_direction),
+            Context::Tablet => match &self.tablet {
+                Some(tablet) => (tablet.smooth, tablet.direction, tablet.gesture_direction),
+                None => return Err(LocomotiveError::UnsupportedContext(context.as_str().to_string())),
+            },
+            Context::Smartphone => match &self.smartphone {
+                Some(smartphone) => (smartphone.smooth, smartphone.direction, smartphone.gesture_direction),
+                None => return Err(LocomotiveError::UnsupportedContext(context.as_str().to_string())),
+            },
+        };
+
+        Ok(ContextOption { smooth, direction, gesture_direction })
+    }
+
+    /// The context given by `is_mobile` and `is_tablet`.
+    pub(crate) fn current_context(&self) -> Context {
+        match (self.is_mobile, self.is_tablet) {
+            (true, true) => Context::Tablet,
+            (true, false) => Context::Smartphone,
+            (false, _) => Context::Desktop,
+        }
+    }
+}
+
+/// Options of `update()`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateOption {
+    /// Keep the current scroll position even if it is now past the new limit.
+    pub preserve_scroll: bool,
+}
+
+impl UpdateOption {
+    pub fn from_js(value: &JsValue) -> Self {
+        if value.is_undefined() || value.is_null() {
+            return UpdateOption::default();
+        }
+
+        serde_wasm_bindgen::from_value(value.clone()).unwrap_or_else(|_| {
+            console::warn_1(&"[options] parameter of update is not valid".into());
+            UpdateOption::default()
+        })
     }
 }
 
@@ -261,6 +654,8 @@ pub struct Names {
     pub offset: String,
     pub target: String,
     pub data_section_inview: String,
+    /// Marks the container of an instance, so an instance around it leaves its elements alone.
+    pub instance: String,
 }
 
 impl Names {
@@ -283,6 +678,53 @@ impl Names {
             offset: format!("{}Offset", name),
             target: format!("{}Target", name),
             data_section_inview: format!("data-{}-section-inview", name),
+            instance: format!("data-{}-instance", name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::de::{value::{StrDeserializer, Error}, IntoDeserializer};
+
+    #[test]
+    fn values_round_trip() {
+        for (name, direction) in GestureDirection::VALUES {
+            assert_eq!(GestureDirection::parse(name), Ok(*direction));
+            assert_eq!(direction.as_str(), *name);
         }
+        assert_eq!(ParallaxPosition::parse("elementLeft"), Ok(ParallaxPosition::ElementLeft));
+        assert_eq!(Direction::Horizontal.axis(), 'x');
+        assert_eq!(Direction::default().axis(), 'y');
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn unknown_values_are_rejected_with_the_allowed_ones() {
+        let err = Direction::parse("diagonal").unwrap_err();
+        assert_eq!(err.to_string(), "expected \"vertical\" or \"horizontal\", got \"diagonal\"");
+        //case matters, like in the JS library
+        assert!(ParallaxPosition::parse("elementtop").is_err());
+    }
+
+    #[test]
+    fn options_deserialize_through_the_same_parser() {
+        let deserializer: StrDeserializer<Error> = "both".into_deserializer();
+        assert_eq!(GestureDirection::deserialize(deserializer), Ok(GestureDirection::Both));
+
+        let deserializer: StrDeserializer<Error> = "sideways".into_deserializer();
+        let err = GestureDirection::deserialize(deserializer).unwrap_err();
+        assert_eq!(err.to_string(), "expected \"vertical\", \"horizontal\" or \"both\", got \"sideways\"");
+    }
+
+    #[test]
+    fn touch_devices_are_tablets_from_the_tablet_breakpoint() {
+        let media = |touch, tablet_width, desktop_width| MediaState { touch, tablet_width, desktop_width };
+        assert_eq!(Context::from_media(media(true, true, true)), Context::Tablet);
+        assert_eq!(Context::from_media(media(true, false, true)), Context::Smartphone);
+        assert_eq!(Context::from_media(media(false, false, true)), Context::Desktop);
+        assert_eq!(Context::from_media(media(false, true, false)), Context::Smartphone);
+        assert_eq!(Context::parse("tablet"), Ok(Context::Tablet));
+        assert!(Context::parse("mobile").is_err());
+    }
+}
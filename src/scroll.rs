@@ -1,57 +1,51 @@
-
-use convert_js::ToJs;
-use wasm_bindgen::JsCast;
+use std::cell::RefMut;
 
 use crate::{smooth::SmoothScroll, native::NativeScroll, option::LocomotiveOption, virtual_scroll::{VirtualScroll, VsOption}};
 
 
+//Only one lives per instance, no need to box it
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone)]
 pub enum Scroll {
     None,
     Smooth(SmoothScroll),
-    _Native(NativeScroll),
+    Native(NativeScroll),
 }
 
 impl Scroll {
 
-    pub fn get_option(&self) -> &LocomotiveOption {
+    /// A snapshot of the options, so no borrow is held while they are used.
+    pub fn get_option(&self) -> LocomotiveOption {
         match self {
             Scroll::Smooth(scroll) => {
-                &scroll.options
+                scroll.options.borrow().clone()
             },
-            Scroll::_Native(scroll) => {
-                &scroll.options
+            Scroll::Native(scroll) => {
+                scroll.options.borrow().clone()
             },
             _ => todo!()
         }
     }
 
-    pub fn get_mut_option(&mut self) -> &mut LocomotiveOption {
+    pub fn get_mut_option(&self) -> RefMut<'_, LocomotiveOption> {
         match self {
-            Scroll::Smooth(scroll) => &mut scroll.options,
-            Scroll::_Native(_) => todo!(),
+            Scroll::Smooth(scroll) => scroll.options.borrow_mut(),
+            Scroll::Native(scroll) => scroll.options.borrow_mut(),
             _ => panic!()
         }
     }
 
     pub fn set_virtual_scroll(&mut self, option: VsOption) {
-        match self {
-            Scroll::Smooth(scroll) => {
-                let vs = VirtualScroll::new(option.to_js());
-                scroll.virtual_scroll = Some(vs);
-            },
-            _ => ()
+        if let Scroll::Smooth(scroll) = self {
+            let vs = VirtualScroll::new(option);
+            scroll.virtual_scroll = Some(vs);
         }
     }
 
     pub fn set_vs_event_listener(&self) {
 
-        match self {
-            Scroll::Smooth(scroll) => {
-                let cb = scroll.vs_cb_1.clone();
-                scroll.virtual_scroll.as_ref().unwrap().on(cb.as_ref().borrow().as_ref().unwrap().as_ref().unchecked_ref());
-            },
-            _ => ()
+        if let Scroll::Smooth(scroll) = self {
+            scroll.virtual_scroll.as_ref().unwrap().on(scroll.vs_cb_1.clone());
         }
     }
     /* 
@@ -81,17 +75,27 @@ impl Scroll {
         }
     }
 
-    pub fn is_smooth(&self) -> bool {
+    pub fn get_native(&self) -> &NativeScroll {
         match self {
-            Scroll::Smooth(_) => true,
-            _ => false,
+            Scroll::Native(scroll) => scroll,
+            _ => panic!("cannot get reference to native scroll"),
         }
     }
 
-    pub fn _is_native(&self) -> bool {
+    pub fn is_smooth(&self) -> bool {
+        matches!(self, Scroll::Smooth(_))
+    }
+
+    /// Whether `stop` got called and no `start` since.
+    pub fn is_stopped(&self) -> bool {
         match self {
-            Scroll::_Native(_) => true,
+            Scroll::Smooth(scroll) => *scroll.stop.borrow(),
+            Scroll::Native(scroll) => *scroll.stop.borrow(),
             _ => false
         }
     }
+
+    pub fn _is_native(&self) -> bool {
+        matches!(self, Scroll::Native(_))
+    }
 }
@@ -2,9 +2,9 @@ use std::{cell::RefCell, rc::Rc};
 
 
 use wasm_bindgen::{prelude::Closure, JsCast};
-use web_sys::{window, KeyboardEvent, WheelEvent, MouseEvent};
+use web_sys::{window, KeyboardEvent, MouseEvent};
 
-use crate::{core::Core, TAB, option::LocomotiveOption};
+use crate::{core::Core, TAB, engine, utils::els::ScrollToInterrupt, virtual_scroll::{VsCallback, VsEvent}};
 
 use super::SmoothScroll;
 
@@ -13,53 +13,75 @@ use super::SmoothScroll;
 impl SmoothScroll {
     pub fn check_key_callback(&mut self, core: Rc<RefCell<Core>>) {
         let callback: Rc<RefCell<Option<Closure<dyn FnMut(KeyboardEvent)>>>> = Rc::new(RefCell::new(None));
+        let options = self.options.clone();
         let stop = self.stop.clone();
         let check_key_cb_1 = self.check_key_cb_1.clone();
+        let check_key_raf = self.check_key_raf.clone();
 
         *callback.borrow_mut() = Some(Closure::new(move |event: KeyboardEvent| {
             let window = window().unwrap();
-            let _core = core.clone();
             let stop = stop.clone();
             let check_key_cb_1 = check_key_cb_1.clone();
             let key = event.key_code();
 
+            //tabbing may scroll the document natively, `focusin` scrolls the focused element into view
+            if key == TAB {
+                *check_key_raf.borrow_mut() = Some(window.request_animation_frame(check_key_cb_1.as_ref().borrow().as_ref().unwrap().as_ref().unchecked_ref()).unwrap());
+                return
+            }
             if *stop.as_ref().borrow() {
-                if key == TAB {
-                    window.request_animation_frame(check_key_cb_1.as_ref().borrow().as_ref().unwrap().as_ref().unchecked_ref()).unwrap();
-                }
                 return
             }
 
-            match key {
-                TAB => {
-                    
-                },
-                _ => {
-
-                }
-            }
+            let options = options.borrow().clone();
+            SmoothScroll::check_key(event, core.clone(), &options);
 
         }));
 
         self.check_key = callback;
     }
 
-    pub fn get_scrollbar(&mut self, core: Rc<RefCell<Core>>, options: &LocomotiveOption) {
+    pub fn check_focus_callback(&mut self) {
+        let callback: Rc<RefCell<Option<Closure<dyn Fn()>>>> = Rc::new(RefCell::new(None));
+        let check_key_cb_2 = self.check_key_cb_2.clone();
+        let focus_raf = self.focus_raf.clone();
+
+        //wait for the browser to be done with the focus before measuring
+        *callback.borrow_mut() = Some(Closure::new(move || {
+            if let Some(handler) = focus_raf.borrow_mut().take() {
+                let _ = window().unwrap().cancel_animation_frame(handler);
+            }
+            *focus_raf.borrow_mut() = Some(window().unwrap().request_animation_frame(check_key_cb_2.borrow().as_ref().unwrap().as_ref().unchecked_ref()).unwrap());
+        }));
+
+        self.check_focus = callback;
+    }
+
+    pub fn get_scrollbar(&mut self, core: Rc<RefCell<Core>>) {
         let callback: Rc<RefCell<Option<Closure<dyn Fn()>>>> = Rc::new(RefCell::new(None));
         let is_dragging_scrollbar = self.is_dragging_scrollbar.clone();
         let html = Core::get_html(core.clone());
-        let options = options.clone();
+        let options = self.options.clone();
+        let animating_scroll = self.animating_scroll.clone();
+        let scroll_to_raf = self.scroll_to_raf.clone();
+        let scroll_to_promise = self.scroll_to_promise.clone();
+        let stop = self.stop.clone();
       
 
         *callback.borrow_mut() = Some(Closure::new(move || {
             let is_dragging_scrollbar = is_dragging_scrollbar.clone();
             let html = html.clone();
-            let options = options.clone();
+            let options = options.borrow().clone();
 
+            if *stop.borrow() {
+                return;
+            }
+
+            SmoothScroll::interrupt_scroll_to(animating_scroll.clone(), scroll_to_raf.clone(), scroll_to_promise.clone(), ScrollToInterrupt::Scrollbar);
             {
                 *is_dragging_scrollbar.as_ref().borrow_mut() = true;
             }
-            SmoothScroll::check_scroll(None, core.clone(), options.clone());
+            SmoothScroll::check_scroll(None, None, core.clone(), options.clone());
             html.as_ref().borrow().class_list().remove_1(&options.scrolling_class).unwrap();
             html.as_ref().borrow().class_list().add_1(&options.dragging_class).unwrap();
         }));
@@ -67,25 +89,24 @@ impl SmoothScroll {
         self.get_scrollbar = callback;
     }
 
-    pub fn release_scrollbar_cb(&mut self, core: Rc<RefCell<Core>>, options: &LocomotiveOption) {
+    pub fn release_scrollbar_cb(&mut self, core: Rc<RefCell<Core>>) {
         let callback: Rc<RefCell<Option<Closure<dyn Fn()>>>> = Rc::new(RefCell::new(None));
-        let scrolling_class = options.scrolling_class.clone();
-        let dragging_class = options.dragging_class.clone();
+        let options = self.options.clone();
       
 
         *callback.borrow_mut() = Some(Closure::new(move || {
             let core = core.clone();
             let core_ref = core.as_ref().borrow();
             let scroll = core_ref.scroll.get_smooth();
-            let scrolling_class = scrolling_class.clone();
-            let dragging_class = dragging_class.clone();
+            let scrolling_class = options.borrow().scrolling_class.clone();
+            let dragging_class = options.borrow().dragging_class.clone();
 
             {
                 *scroll.is_dragging_scrollbar.borrow_mut() = false;
             }
 
             {
-                if *scroll.is_scrolling.as_ref().borrow() == true {
+                if *scroll.is_scrolling.as_ref().borrow() {
                     core_ref.html.borrow().class_list().add_1(&scrolling_class).unwrap()
                 }
             }
@@ -110,7 +131,7 @@ impl SmoothScroll {
             }
 
             if *is_dragging_scrollbar.borrow() {
-                window().unwrap().request_animation_frame(scroll.move_scrollbar_cb_2.borrow().as_ref().unwrap().as_ref().unchecked_ref()).unwrap();
+                *scroll.move_scrollbar_raf.borrow_mut() = Some(window().unwrap().request_animation_frame(scroll.move_scrollbar_cb_2.borrow().as_ref().unwrap().as_ref().unchecked_ref()).unwrap());
             }
         }));
 
@@ -165,64 +186,87 @@ impl SmoothScroll {
     pub fn check_key_cb_2(&mut self, core: Rc<RefCell<Core>>) {
         let callback: Rc<RefCell<Option<Closure<dyn Fn()>>>> = Rc::new(RefCell::new(None));
         let html = Core::get_html(core.clone());
+        let stop = self.stop.clone();
+        let focus_raf = self.focus_raf.clone();
+        let options = self.options.clone();
 
         *callback.borrow_mut() = Some(Closure::new(move || {
             let html = html.clone();
-            let body = window().unwrap().document().unwrap().body().unwrap();
+            let document = window().unwrap().document().unwrap();
+            let body = document.body().unwrap();
+            {
+                *focus_raf.borrow_mut() = None;
+            }
 
             html.as_ref().borrow().set_scroll_top(0);
             body.set_scroll_top(0);
             html.as_ref().borrow().set_scroll_left(0);
             body.set_scroll_left(0);
 
-            //Core::scroll_to(scroll, target, scroll_to_option, html, instance)
+            if *stop.borrow() {
+                return;
+            }
+            //a click focuses too, only the keyboard focus is brought into view
+            if let Some(active_element) = document.active_element() {
+                if active_element != *body.as_ref() && active_element.matches(":focus-visible").unwrap_or(true) {
+                    let options = options.borrow().clone();
+                    SmoothScroll::scroll_into_view(&active_element, core.clone(), &options);
+                }
+            }
         }));
 
-        self.check_key_cb_1 = callback;
+        self.check_key_cb_2 = callback;
     }
 
-    pub fn vs_cb_1(&mut self, _core: Rc<RefCell<Core>>, _options: &LocomotiveOption) {
-        let callback: Rc<RefCell<Option<Closure<dyn Fn(WheelEvent)>>>> = Rc::new(RefCell::new(None));
+    pub fn vs_cb_1(&mut self) {
+        let callback: Rc<RefCell<Option<VsCallback>>> = Rc::new(RefCell::new(None));
         let stop = self.stop.clone();
         let is_dragging_scrollbar = self.is_dragging_scrollbar.clone();
-        let wheel_event = self.wheel_event.clone();
+        let vs_events = self.vs_events.clone();
         let cb = self.vs_cb_2.clone();
+        let animating_scroll = self.animating_scroll.clone();
+        let scroll_to_raf = self.scroll_to_raf.clone();
+        let scroll_to_promise = self.scroll_to_promise.clone();
+        let vs_raf = self.vs_raf.clone();
 
 
-        *callback.borrow_mut() = Some(Closure::new(move |event: WheelEvent| {
-            let stop = stop.clone();
-            let is_dragging_scrollbar = is_dragging_scrollbar.clone();
-            let wheel_event = wheel_event.clone();
-            {
-                *wheel_event.borrow_mut() = Some(event.clone());
+        *callback.borrow_mut() = Some(VsCallback(Box::new(move |event: &VsEvent| {
+            if *stop.borrow() || *is_dragging_scrollbar.borrow() {
+                return;
             }
-            let cb = cb.clone();
 
-            if *stop.as_ref().borrow() == false {
-                if *is_dragging_scrollbar.as_ref().borrow() == false {
+            SmoothScroll::interrupt_scroll_to(animating_scroll.clone(), scroll_to_raf.clone(), scroll_to_promise.clone(), ScrollToInterrupt::Wheel);
 
-                    window().unwrap().request_animation_frame(cb.borrow().as_ref().unwrap().as_ref().unchecked_ref()).unwrap();
-                }
+            //every event of the frame is applied, not only the last one
+            {
+                vs_events.borrow_mut().push(event.clone());
             }
-        }));
+            if vs_raf.borrow().is_none() {
+                *vs_raf.borrow_mut() = Some(window().unwrap().request_animation_frame(cb.borrow().as_ref().unwrap().as_ref().unchecked_ref()).unwrap());
+            }
+        })));
 
         self.vs_cb_1 = callback;
     }
 
-    pub fn vs_cb_2(&mut self, core: Rc<RefCell<Core>>, options: &LocomotiveOption) {
+    pub fn vs_cb_2(&mut self, core: Rc<RefCell<Core>>) {
         let callback: Rc<RefCell<Option<Closure<dyn Fn()>>>> = Rc::new(RefCell::new(None));
-        let wheel_event = self.wheel_event.clone();
-        let options = options.clone();
+        let vs_events = self.vs_events.clone();
+        let vs_raf = self.vs_raf.clone();
+        let options = self.options.clone();
         let is_scrolling = self.is_scrolling.clone();
         
         *callback.borrow_mut() = Some(Closure::new(move || {
-            let core = core.clone();
-            let wheel_event = wheel_event.clone();
-            let wheel_event = wheel_event.borrow().as_ref().unwrap().clone();
-            let options = options.clone();
+            {
+                *vs_raf.borrow_mut() = None;
+            }
+            let events = vs_events.take();
+            let options = options.borrow().clone();
 
-            SmoothScroll::update_delta(wheel_event, core.clone(), options.clone());
-            if *is_scrolling.as_ref().borrow() == false {
+            for event in events.iter() {
+                SmoothScroll::update_delta(event, core.clone(), options.clone());
+            }
+            if !*is_scrolling.borrow() {
                 SmoothScroll::start_scrolling(core.clone(), options.clone());
             }
         }));
@@ -230,14 +274,14 @@ impl SmoothScroll {
         self.vs_cb_2 = callback;
     }
 
-    pub fn check_scroll_cb(&mut self, core: Rc<RefCell<Core>>, options: &LocomotiveOption) {
-        let callback: Rc<RefCell<Option<Closure<dyn Fn()>>>> = Rc::new(RefCell::new(None));
-        let options = options.clone();
+    pub fn check_scroll_cb(&mut self, core: Rc<RefCell<Core>>) {
+        let callback: Rc<RefCell<Option<Closure<dyn Fn(f64)>>>> = Rc::new(RefCell::new(None));
+        let options = self.options.clone();
 
-        *callback.borrow_mut() = Some(Closure::new(move || {
-            let options = options.clone();
+        *callback.borrow_mut() = Some(Closure::new(move |timestamp: f64| {
+            let options = options.borrow().clone();
             let core = core.clone();
-            SmoothScroll::check_scroll(None, core, options);
+            SmoothScroll::check_scroll(None, Some(timestamp), core, options);
         }));
 
         self.check_scroll_cb = callback;
@@ -268,13 +312,13 @@ impl SmoothScroll {
             let scrollbar_height = scrollbar_height.borrow();
             let scrollbar_height = scrollbar_height.as_ref().unwrap();
 
-            let x = ((((event.client_x() as f64 - scrollbar_bcr.left()) * 100.0) / scrollbar_width) * instance.limit.x) / 100.0;
-            let y = ((((event.client_y() as f64 - scrollbar_bcr.top()) * 100.0) / scrollbar_height) * instance.limit.y) / 100.0;
+            let x = engine::scrollbar_drag(event.client_x() as f64 - scrollbar_bcr.left(), *scrollbar_width, instance.limit.x);
+            let y = engine::scrollbar_drag(event.client_y() as f64 - scrollbar_bcr.top(), *scrollbar_height, instance.limit.y);
 
-            if y > 0.0 && y < instance.limit.y {
+            if let Some(y) = y {
                 instance.delta.as_mut().unwrap().y = y;
             }
-            if x > 0.0 && x < instance.limit.x {
+            if let Some(x) = x {
                 instance.delta.as_mut().unwrap().x = x;
             }
         }));
@@ -1,37 +1,46 @@
 mod callback;
 mod utils;
+mod physics;
 
 use std::{rc::Rc, cell::RefCell};
 
-use js_sys::{Date, Function};
-use wasm_bindgen::{prelude::Closure, JsCast, JsValue};
-use web_sys::{Element, KeyboardEvent, window, WheelEvent, HtmlElement, Node, MouseEvent, DomRect};
+use convert_js::ToJs;
+use js_sys::{Date, Function, Promise};
+use wasm_bindgen::{prelude::Closure, JsCast};
+use web_sys::{console, Element, KeyboardEvent, window, HtmlElement, Node, MouseEvent, DomRect};
 
-use crate::{option::{LocomotiveOption, Position}, virtual_scroll::{VirtualScroll, VsOption},core::Core, utils::{instance::Instance, lerp, get_translate, get_parents, els::{MappedEl, ScrollToTarget, ScrollToOption}}, bezier_easing::bezier2};
+use crate::{error::LocomotiveError, option::{LocomotiveOption, Position, UpdateOption, Physics, Direction, ParallaxPosition, OptionValue}, engine::{self, Parallax, Rect}, virtual_scroll::{VirtualScroll, VsOption, VsCallback, VsEvent},core::Core, utils::{instance::Instance, listeners::ListenerEvent, get_translate, get_target_el, now, get_parents, els::{MappedEl, ScrollToTarget, ScrollToOption, ScrollToPromise, ScrollToInterrupt}}, SPACE};
 
-pub use self::utils::{Sections, Section, ParallaxElements};
+pub use self::utils::{Sections, Section, ParallaxElements, get_gesture_direction};
+pub use self::physics::Integrator;
 
 
 
 #[derive(Debug, Clone)]
 pub struct SmoothScroll {
-    pub options: LocomotiveOption,
+    /// Shared with the core, `setOptions` changes are seen by every closure.
+    pub options: Rc<RefCell<LocomotiveOption>>,
     pub is_scrolling: Rc<RefCell<bool>>,
     pub is_dragging_scrollbar: Rc<RefCell<bool>>,
-    pub is_ticking: bool,
     pub parallax_elements: Rc<RefCell<ParallaxElements>>,
     pub stop: Rc<RefCell<bool>>,
-    pub scrollbar_container: bool,
     pub check_key: Rc<RefCell<Option<Closure<dyn FnMut(KeyboardEvent)>>>>,
+    pub check_focus: Rc<RefCell<Option<Closure<dyn Fn()>>>>,
 
     pub virtual_scroll: Option<VirtualScroll>,
     pub animating_scroll: Rc<RefCell<bool>>,
+    pub integrator: Rc<RefCell<Integrator>>,
 
     //stop scrolling
     pub check_scroll_raf: Rc<RefCell<Option<i32>>>,
     pub start_scroll_ts: Rc<RefCell<Option<f64>>>,
     pub speed_ts: Rc<RefCell<Option<f64>>>,
     pub scroll_to_raf: Rc<RefCell<Option<i32>>>,
+    pub scroll_to_promise: Rc<RefCell<Option<ScrollToPromise>>>,
+    pub check_key_raf: Rc<RefCell<Option<i32>>>,
+    pub focus_raf: Rc<RefCell<Option<i32>>>,
+    pub vs_raf: Rc<RefCell<Option<i32>>>,
+    pub move_scrollbar_raf: Rc<RefCell<Option<i32>>>,
 
     //checkScroll
     pub sections: Rc<RefCell<Sections>>,
@@ -43,7 +52,7 @@ pub struct SmoothScroll {
     pub release_scrollbar: Rc<RefCell<Option<Closure<dyn Fn()>>>>,
     pub move_scrollbar: Rc<RefCell<Option<Closure<dyn Fn(MouseEvent)>>>>,
     pub mouse_event: Rc<RefCell<Option<MouseEvent>>>,
-    pub wheel_event: Rc<RefCell<Option<WheelEvent>>>,
+    pub vs_events: Rc<RefCell<Vec<VsEvent>>>,
     pub scrollbar_bcr: Rc<RefCell<Option<DomRect>>>,
     pub scrollbar_width: Rc<RefCell<Option<f64>>>,
     pub scrollbar_height: Rc<RefCell<Option<f64>>>,
@@ -53,40 +62,37 @@ pub struct SmoothScroll {
     //Closures / Function
     check_key_cb_1: Rc<RefCell<Option<Closure<dyn Fn()>>>>,
     pub check_key_cb_2: Rc<RefCell<Option<Closure<dyn Fn()>>>>,
-    pub vs_cb_1: Rc<RefCell<Option<Closure<dyn Fn(WheelEvent)>>>>,
+    pub vs_cb_1: Rc<RefCell<Option<VsCallback>>>,
     pub vs_cb_2: Rc<RefCell<Option<Closure<dyn Fn()>>>>,
-    pub check_scroll_cb: Rc<RefCell<Option<Closure<dyn Fn()>>>>,
+    pub check_scroll_cb: Rc<RefCell<Option<Closure<dyn Fn(f64)>>>>,
     pub move_scrollbar_cb_2: Rc<RefCell<Option<Closure<dyn Fn()>>>>,
     pub loop_cb: Rc<RefCell<Option<Closure<dyn Fn()>>>>,
 }
 
 impl SmoothScroll {
-    pub fn new(mut options: LocomotiveOption, core: Rc<RefCell<Core>>) -> Self {
-
-        //1
-        if let Some(inertia) = options.inertia {
-            options.lerp = inertia * 0.1;
-        }
-
-        let scrollbar_container = options.scroll_bar_container;
-
+    pub fn new(options: Rc<RefCell<LocomotiveOption>>, core: Rc<RefCell<Core>>) -> Self {
         let mut smooth = Self {
-            options: options.clone(),
+            options,
             is_scrolling: Rc::new(RefCell::new(false)),
             is_dragging_scrollbar: Rc::new(RefCell::new(false)),
-            is_ticking: false,
             parallax_elements: Rc::new(RefCell::new(ParallaxElements::new())),
             stop: Rc::new(RefCell::new(false)),
-            scrollbar_container,
             check_key: Rc::new(RefCell::new(None)),
+            check_focus: Rc::new(RefCell::new(None)),
 
             virtual_scroll: None,
             animating_scroll: Rc::new(RefCell::new(false)),
+            integrator: Rc::new(RefCell::new(Integrator::default())),
 
             check_scroll_raf: Rc::new(RefCell::new(None)),
             start_scroll_ts: Rc::new(RefCell::new(None)),
             speed_ts: Rc::new(RefCell::new(None)),
             scroll_to_raf: Rc::new(RefCell::new(None)),
+            scroll_to_promise: Rc::new(RefCell::new(None)),
+            check_key_raf: Rc::new(RefCell::new(None)),
+            focus_raf: Rc::new(RefCell::new(None)),
+            vs_raf: Rc::new(RefCell::new(None)),
+            move_scrollbar_raf: Rc::new(RefCell::new(None)),
             sections: Rc::new(RefCell::new(Sections::new())),
 
             scrollbar: Rc::new(RefCell::new(None)),
@@ -95,7 +101,7 @@ impl SmoothScroll {
             release_scrollbar: Rc::new(RefCell::new(None)),
             move_scrollbar: Rc::new(RefCell::new(None)),
             mouse_event: Rc::new(RefCell::new(None)),
-            wheel_event: Rc::new(RefCell::new(None)),
+            vs_events: Rc::new(RefCell::new(vec![])),
             scrollbar_bcr: Rc::new(RefCell::new(None)),
             scrollbar_width: Rc::new(RefCell::new(None)),
             scrollbar_height: Rc::new(RefCell::new(None)),
@@ -113,11 +119,12 @@ impl SmoothScroll {
         smooth.check_key_cb_1(core.clone());
         smooth.check_key_cb_2(core.clone());
         smooth.check_key_callback(core.clone());
-        smooth.vs_cb_2(core.clone(), &options);
-        smooth.vs_cb_1(core.clone(), &options);
-        smooth.check_scroll_cb(core.clone(), &options);
-        smooth.get_scrollbar(core.clone(), &options);
-        smooth.release_scrollbar_cb(core.clone(), &options);
+        smooth.check_focus_callback();
+        smooth.vs_cb_2(core.clone());
+        smooth.vs_cb_1();
+        smooth.check_scroll_cb(core.clone());
+        smooth.get_scrollbar(core.clone());
+        smooth.release_scrollbar_cb(core.clone());
         smooth.move_scrollbar_cb_2(core.clone());
         smooth.move_scrollbar_cb(core.clone());
         
@@ -126,10 +133,10 @@ impl SmoothScroll {
     }
 
 
-    pub fn init(core: Rc<RefCell<Core>>, options: &LocomotiveOption) {
+    pub fn init(core: Rc<RefCell<Core>>, options: &LocomotiveOption) -> Result<(), LocomotiveError> {
         {
             let _ = core.as_ref().borrow().html.as_ref().borrow().class_list().add_1(&options.smooth_class);
-            let _ = core.as_ref().borrow().html.as_ref().borrow().set_attribute(&options.names.as_ref().unwrap().data_direction, &options.direction);
+            let _ = core.as_ref().borrow().html.as_ref().borrow().set_attribute(&options.names.as_ref().unwrap().data_direction, options.direction.as_str());
         }
         {  
             {
@@ -140,25 +147,18 @@ impl SmoothScroll {
             }
         }
         {
-            let el = if options.scroll_from_anywhere {
-                window().unwrap().document().unwrap().dyn_into::<JsValue>().unwrap()
-            } else {
-                options.el.clone().to_js()
-            };
-            let mouse_multiplier = if window().unwrap().navigator().platform().unwrap().contains("Win") {1.0} else {0.4};
-            let vs_option = VsOption {
-                el,
-                mouse_multiplier,
-                firefox_multiplier: options.firefox_multiplier,
-                touch_multiplier: options.touch_multiplier,
-                use_keyboard: false,
-                passive: true,
-            };
-            core.borrow_mut().scroll.set_virtual_scroll(vs_option);
+            core.borrow_mut().scroll.set_virtual_scroll(VsOption::new(options));
         }
         {
             core.as_ref().borrow().scroll.set_vs_event_listener()
         }
+        {
+            let core_ref = core.borrow();
+            let scroll = core_ref.scroll.get_smooth();
+            let window = window().unwrap();
+            window.add_event_listener_with_callback("keydown", scroll.check_key.borrow().as_ref().unwrap().as_ref().unchecked_ref()).unwrap();
+            window.add_event_listener_with_callback("focusin", scroll.check_focus.borrow().as_ref().unwrap().as_ref().unchecked_ref()).unwrap();
+        }
         {
             Self::set_scroll_limit(core.clone(), options);
         }
@@ -169,16 +169,17 @@ impl SmoothScroll {
             Self::add_sections(core.clone(), options);
         }
         {
-            Self::add_elements(core.clone(), options);
+            Self::add_elements(core.clone(), options)?;
         }
 
         {
-            Self::check_scroll(Some(true), core.clone(), options.clone());
+            Self::check_scroll(Some(true), None, core.clone(), options.clone());
         }
         {
             Self::transform_elements(Some(true), Some(true), core.clone(), options)
         }
 
+        Ok(())
     }
 }
 
@@ -190,7 +191,10 @@ impl SmoothScroll {
         let window_width = ref_core.window_width;
         let mut instance = ref_core.instance.as_ref().borrow_mut();
 
-        if options.direction.as_str() == "horizontal" {
+        let el_height = options.el.get_element().dyn_ref::<HtmlElement>().unwrap().offset_height();
+        instance.limit.y = el_height as f64 - ref_core.window_height;
+
+        if options.direction == Direction::Horizontal {
             let mut total_width = 0;
             let nodes = options.el.children();
             for i in 0 .. nodes.length() {
@@ -217,9 +221,10 @@ impl SmoothScroll {
 
         scrollbar.append_with_node_1(scrollbar_thumb.dyn_ref::<Node>().unwrap()).unwrap();
 
-        //todo..append to scrollbar container if exist
-        //...
-        doc.body().unwrap().append_with_node_1(scrollbar.dyn_ref::<Node>().unwrap()).unwrap();
+        match &options.scroll_bar_container {
+            Some(container) => container.append_with_node_1(scrollbar.dyn_ref::<Node>().unwrap()).unwrap(),
+            None => doc.body().unwrap().append_with_node_1(scrollbar.dyn_ref::<Node>().unwrap()).unwrap(),
+        }
         {
             *scroll.scrollbar.clone().borrow_mut() = Some(scrollbar);
             *scroll.scrollbar_thumb.borrow_mut() = Some(scrollbar_thumb.clone());            
@@ -234,7 +239,7 @@ impl SmoothScroll {
         {
             *core.borrow().has_scroll_bar.borrow_mut() = Some(false);
         }
-        if options.direction.as_str() == "horizontal" {
+        if options.direction == Direction::Horizontal {
             if instance.borrow().limit.x + core.borrow().window_width <= core.borrow().window_width {
                 return;
             }
@@ -255,18 +260,18 @@ impl SmoothScroll {
             *scroll.scrollbar_width.borrow_mut() = Some(scroll.scrollbar_bcr.borrow().as_ref().unwrap().width());
         }
         {
-            if options.direction.as_str() == "horizontal" {
+            if options.direction == Direction::Horizontal {
                 let style = scroll.scrollbar_thumb.borrow().as_ref().unwrap().dyn_ref::<HtmlElement>().unwrap().style();
                 let scrollbar_width = scroll.scrollbar_width.borrow();
                 let scrollbar_width = scrollbar_width.as_ref().unwrap();
-                let width = (scrollbar_width * scrollbar_width) / instance.borrow().limit.x + scrollbar_width;
+                let width = engine::thumb_size(*scrollbar_width, instance.borrow().limit.x);
                 style.set_property("width", &format!("{:?}px", width)).unwrap();
             } else {
                 let style = scroll.scrollbar_thumb.borrow().as_ref().unwrap().dyn_ref::<HtmlElement>().unwrap().style();
                 let scrollbar_height = scroll.scrollbar_height.borrow();
                 let scrollbar_height = scrollbar_height.as_ref().unwrap();
-                let width = (scrollbar_height * scrollbar_height) / instance.borrow().limit.y + scrollbar_height;
-                style.set_property("height", &format!("{:?}px", width)).unwrap();
+                let height = engine::thumb_size(*scrollbar_height, instance.borrow().limit.y);
+                style.set_property("height", &format!("{:?}px", height)).unwrap();
             }
         }
 
@@ -290,16 +295,11 @@ impl SmoothScroll {
             scroll.sections.borrow_mut().clear();
         }
 
-        let sections = options.el.query_selector_all(&options.names.as_ref().unwrap().data_section).unwrap();
-        let sections = if sections.length() == 0 {
+        let sections = options.query_own(&options.names.as_ref().unwrap().data_section);
+        let sections = if sections.is_empty() {
             vec![options.el.get_element().clone()]
         } else {
-            let mut res = vec![];
-            for i in 0 .. sections.length() {
-                let node = sections.get(i).unwrap();
-                res.push(node.dyn_into::<Element>().unwrap());
-            }
-            res
+            sections
         };
     
         for (index, section) in sections.into_iter().enumerate() {
@@ -339,7 +339,8 @@ impl SmoothScroll {
     }
 
      
-    fn add_elements(core: Rc<RefCell<Core>>, options: &LocomotiveOption) {
+    fn add_elements(core: Rc<RefCell<Core>>, options: &LocomotiveOption) -> Result<(), LocomotiveError> {
+        let names = options.names.as_ref().unwrap();
         let core_ref = core.as_ref().borrow();
         let scroll = core_ref.scroll.get_smooth();
         let sections = scroll.sections.clone();
@@ -350,11 +351,9 @@ impl SmoothScroll {
             scroll.parallax_elements.borrow_mut().data.clear();
         }
 
-        let els = options.el.query_selector_all(&options.names.as_ref().unwrap().data).unwrap();
+        let els = options.query_own(&options.names.as_ref().unwrap().data);
 
-        for index in 0 .. els.length() {
-            let node = els.get(index).unwrap();
-            let el = node.dyn_ref::<Element>().unwrap();
+        for (index, el) in els.iter().enumerate() {
             let html_el = el.dyn_ref::<HtmlElement>().unwrap();
             let dataset = html_el.dataset();
             let parents = get_parents(el.clone());
@@ -370,7 +369,7 @@ impl SmoothScroll {
             let id = if let Some(el_id) = dataset.get(&options.names.as_ref().unwrap().id) {
                 el_id
             } else {
-                format!("el{}", index.to_string())
+                format!("el{}", index)
             };
             let repeat = match dataset.get(&options.names.as_ref().unwrap().repeat) {
                 Some(val) => {
@@ -382,31 +381,36 @@ impl SmoothScroll {
                 None => options.repeat
             };
             let call = dataset.get(&options.names.as_ref().unwrap().call);
-            let position = dataset.get(&options.names.as_ref().unwrap().position);
-            let delay = dataset.get(&options.names.as_ref().unwrap().delay);
-            let direction = dataset.get(&options.names.as_ref().unwrap().direction);
+            let position = dataset.get(&names.position)
+                .map(|val| ParallaxPosition::parse(&val)).transpose()
+                .map_err(|err| err.into_attribute_error(&names.position))?;
+            let delay = dataset.get(&names.delay);
+            if let Some(val) = delay.as_ref().filter(|val| val.parse::<f64>().is_err()) {
+                return Err(LocomotiveError::InvalidAttribute { name: names.delay.clone(), value: val.clone(), expected: "a number".to_string() });
+            }
+            let direction = dataset.get(&names.direction)
+                .map(|val| Direction::parse(&val)).transpose()
+                .map_err(|err| err.into_attribute_error(&names.direction))?;
             let sticky = dataset.get(&options.names.as_ref().unwrap().sticky);
-            let speed = if let Some(val) = dataset.get(&options.names.as_ref().unwrap().speed) {
-                Some(val.parse::<f64>().unwrap() / 10.0)
-            } else {
-                None
+            let speed = match dataset.get(&names.speed) {
+                Some(val) => match val.parse::<f64>() {
+                    Ok(speed) => Some(speed / 10.0),
+                    Err(_) => return Err(LocomotiveError::InvalidAttribute { name: names.speed.clone(), value: val, expected: "a number".to_string() }),
+                },
+                None => None
             };
             let offset = if let Some(val) = dataset.get(&options.names.as_ref().unwrap().offset) {
                 val.split(",").map(|s| s.trim().to_string()).collect::<Vec<String>>()
             } else {
                 vec![options.offset[0].to_string(), options.offset[1].to_string()]
             };
-            let target = dataset.get(&options.names.as_ref().unwrap().target);
-            let target_el = match target {
-                Some(val) => window().unwrap().document().unwrap().query_selector(&val).unwrap().unwrap(),
-                None => el.clone()
-            };
+            let target_el = get_target_el(el, dataset.get(&names.target), &names.target)?;
             let target_el_html = target_el.dyn_ref::<HtmlElement>().unwrap();
 
             let target_el_bcr = target_el.get_bounding_client_rect();
             let (mut top, mut left) = match section {
                 Some(sect) => {
-                    match !sect.borrow().in_view.clone() {
+                    match !sect.borrow().in_view {
                         true => {
                             let top = target_el_bcr.top() - get_translate(&sect.borrow().el).y + get_translate(&target_el).y;
                             let left = target_el_bcr.left() - get_translate(&sect.borrow().el).x + get_translate(&target_el).x;
@@ -454,30 +458,9 @@ impl SmoothScroll {
 
             }
 
-            let mut relative_offset = [0.0, 0.0];
-            if options.direction.as_str() == "horizontal" {
-                for (index, val) in offset.iter().enumerate() {
-                    let val_num = if val.contains("%") {
-                        (val.replace("%", "").parse::<f64>().unwrap() * core_ref.window_width) / 100.0
-                    } else {
-                        val.parse::<f64>().unwrap()
-                    };
-                    relative_offset[index] = val_num;
-                } 
-                left = left + relative_offset[0];
-                right = right - relative_offset[1];
-            } else {
-                for (index, val) in offset.iter().enumerate() {
-                    let val_num = if val.contains("%") {
-                        (val.replace("%", "").parse::<f64>().unwrap() * core_ref.window_width) / 100.0
-                    } else {
-                        val.parse::<f64>().unwrap()
-                    };
-                    relative_offset[index] = val_num;
-                } 
-                top = top + relative_offset[0];
-                bottom = bottom - relative_offset[1]; 
-            }
+            let window_size = Position::new(core_ref.window_width, core_ref.window_height);
+            let relative_offset = engine::relative_offset(&offset, window_size.get(direction_axis));
+            let Rect { top, bottom, left, right } = Rect::new(top, bottom, left, right).with_offset(relative_offset, direction_axis);
 
             let mapped_el = MappedEl {
                 el: Some(el.clone()),
@@ -493,7 +476,7 @@ impl SmoothScroll {
                 repeat: Some(repeat),
                 in_view: Some(false),
                 call,
-                section: Some(section.unwrap().clone()),
+                section: section.cloned(),
                 middle: Some(middle),
                 speed,
                 delay,
@@ -506,14 +489,16 @@ impl SmoothScroll {
                 core_ref.els.clone().borrow_mut().data.entry(id.clone()).or_insert(mapped_el.clone());
             }
             if el.class_list().contains(&cl) {
-                Core::set_in_view(&mut mapped_el.borrow_mut(), &id, core.clone(), options);
+                Core::set_in_view(&mut mapped_el.borrow_mut(), &id, core.clone(), options, false);
             }
-            if speed != None || sticky.is_some() {
+            if speed.is_some() || sticky.is_some() {
                 {   
                     scroll.parallax_elements.borrow_mut().data.insert(id, mapped_el);
                 }
             }
         }
+
+        Ok(())
     }
     
 }
@@ -523,25 +508,39 @@ impl SmoothScroll {
 
 
 impl SmoothScroll {
-    pub fn update_scroll(core: Rc<RefCell<Core>>, lerp_val: f64) {
+    pub fn update_scroll(core: Rc<RefCell<Core>>, options: &LocomotiveOption, timestamp: f64) {
         let ref_core = core.clone();
         let ref_core = &ref_core.as_ref().borrow().scroll;
         let scroll = ref_core.get_smooth();
         let is_scrolling = scroll.is_scrolling.clone();
         let is_dragging_scrollbar = scroll.is_dragging_scrollbar.clone();
+        let integrator = scroll.integrator.clone();
         let instance = core.as_ref().borrow().instance.clone();
         let direction_axis = *core.as_ref().borrow().direction_axis.clone().borrow();
 
         if *is_scrolling.borrow() || *is_dragging_scrollbar.borrow() {
-            {   
-                let new_val = lerp(
-                    instance.borrow().scroll.get(direction_axis),
-                    instance.borrow().delta.as_ref().unwrap().get(direction_axis),
-                    lerp_val
-                );
-                instance.borrow_mut().scroll.set(new_val, direction_axis);
-            }
+            //`scrollTo` moves the target itself, coasting past it would overshoot
+            let physics = match &options.physics {
+                Physics::Friction(_) if *scroll.animating_scroll.borrow() => &Physics::Lerp,
+                physics => physics,
+            };
+
+            let mut integrator = integrator.borrow_mut();
+            let dt = integrator.frame_dt(timestamp);
+            let mut instance = instance.borrow_mut();
+            let (new_val, target) = integrator.step(
+                physics,
+                options.lerp,
+                dt,
+                instance.scroll.get(direction_axis),
+                instance.delta.as_ref().unwrap().get(direction_axis),
+                instance.limit.get(direction_axis)
+            );
+            instance.scroll.set(new_val, direction_axis);
+            instance.delta.as_mut().unwrap().set(target, direction_axis);
         } else {
+            integrator.borrow_mut().reset();
+
             let (scroll, limit, delta, scroll_y) = {(
                 instance.borrow().scroll.get(direction_axis),
                 instance.borrow().limit.get(direction_axis),
@@ -558,6 +557,21 @@ impl SmoothScroll {
         }
     }
 
+    /// Bring `scroll` and `delta` back within `[0, limit]` on the scroll axis.
+    fn clamp_scroll(core: Rc<RefCell<Core>>) {
+        let core_ref = core.borrow();
+        let direction_axis = *core_ref.direction_axis.borrow();
+        let mut instance = core_ref.instance.borrow_mut();
+        let limit = instance.limit.get(direction_axis);
+
+        let scroll_val = engine::clamp(instance.scroll.get(direction_axis), limit);
+        instance.scroll.set(scroll_val, direction_axis);
+        if let Some(delta) = instance.delta.as_mut() {
+            let delta_val = engine::clamp(delta.get(direction_axis), limit);
+            delta.set(delta_val, direction_axis);
+        }
+    }
+
     pub fn set_scroll(instance: Rc<RefCell<Instance>>, x: f64, y: f64) {
         let mut instance = instance.as_ref().borrow_mut();
         instance.set_scroll(Position::new(x, y));
@@ -569,7 +583,8 @@ impl SmoothScroll {
         scroll_to_raf: Rc<RefCell<Option<i32>>>, is_scrolling: Rc<RefCell<bool>>, scrolling_class: String
     ) {
         let instance = core.as_ref().borrow().instance.clone();
-        let html = &core.as_ref().borrow().html;
+        let html = core.as_ref().borrow().html.clone();
+        core.borrow().scroll.get_smooth().integrator.borrow_mut().reset();
         if let Some(handler) = check_scroll_raf.as_ref().clone().into_inner() {
             window().unwrap().cancel_animation_frame(handler).unwrap();
         } 
@@ -584,14 +599,28 @@ impl SmoothScroll {
             *scroll_to_raf.as_ref().borrow_mut() = None;
         } 
 
-        *is_scrolling.as_ref().borrow_mut() = false;
-        let mut instance = instance.borrow_mut();
-        instance.scroll.y = instance.scroll.y.round();
+        let was_scrolling = is_scrolling.replace(false);
+        {
+            let mut instance = instance.borrow_mut();
+            instance.scroll.y = instance.scroll.y.round();
+        }
         html.as_ref().borrow().class_list().remove_1(&scrolling_class).unwrap(); 
+
+        if was_scrolling {
+            Core::emit(core, ListenerEvent::ScrollEnd);
+        }
     }
 
-    pub fn check_scroll(forced: Option<bool>, core: Rc<RefCell<Core>>, option: LocomotiveOption) {
+    /// `timestamp` is the one given by `requestAnimationFrame`, `now()` when called from elsewhere.
+    pub fn check_scroll(forced: Option<bool>, timestamp: Option<f64>, core: Rc<RefCell<Core>>, option: LocomotiveOption) {
+        SmoothScroll::tick(forced, timestamp, core.clone(), option);
+        //the calls and the scroll are emitted once the frame released the core
+        Core::flush(&core);
+    }
+
+    fn tick(forced: Option<bool>, timestamp: Option<f64>, core: Rc<RefCell<Core>>, option: LocomotiveOption) {
         let forced = forced.unwrap_or(false);
+        let timestamp = timestamp.unwrap_or_else(now);
         let ref_core = core.clone();
         let has_scroll_ticking = core.borrow().has_scroll_ticking.clone();
         let scroll = &ref_core.as_ref().borrow().scroll;
@@ -621,19 +650,16 @@ impl SmoothScroll {
             }
             
 
-            SmoothScroll::update_scroll(core.clone(),  option.lerp);
+            SmoothScroll::update_scroll(core.clone(), &option, timestamp);
 
             let distance = (delta - scroll_val).abs();
-            let time_since_start = if let Some(val) = start_scroll_ts.borrow().clone() {
-                Some(Date::now() - val)
-            } else {
-                None
-            };
+            let time_since_start = (*start_scroll_ts.borrow()).map(|val| timestamp - val);
 
             if !*animating_scroll.borrow() && 
             time_since_start.is_some() &&
             time_since_start.unwrap() > 100.0 &&
-            ((distance < 0.5 && delta != 0.0) || (distance < 0.5 && delta == 0.0)) {
+            distance < 0.5 &&
+            !scroll.integrator.borrow().is_moving() {
                 SmoothScroll::stop_scrolling(core.clone(), start_scroll_ts.clone(), check_scroll_raf.clone(), scroll_to_raf.clone(), is_scrolling.clone(), option.scrolling_class.clone());
             }
             /* 
@@ -642,7 +668,7 @@ impl SmoothScroll {
             web_sys::console::log_1(&dbg.into());
             */
 
-            for (_id, section) in &scroll.sections.as_ref().borrow().data {
+            for section in scroll.sections.as_ref().borrow().data.values() {
                 let mut section = section.as_ref().borrow_mut();
                 let (offset, section_limit) = match direction_axis {
                     'x' => (section.offset.x, section.limit.x),
@@ -650,10 +676,10 @@ impl SmoothScroll {
                     _ => panic!()
                 };
 
-                if section.persistent.is_some() || (scroll_val > offset && scroll_val < section_limit) {
-                    match option.direction.as_str() {
-                        "horizontal" => {
-                            SmoothScroll::transform(section.el.clone(), Some(scroll_val * -1.0), Some(0.0), None);
+                if engine::section_in_view(offset, section_limit, section.persistent.is_some(), scroll_val) {
+                    match option.direction {
+                        Direction::Horizontal => {
+                            SmoothScroll::transform(section.el.clone(), Some(-scroll_val), Some(0.0), None);
                         },
                         _ => {
                             SmoothScroll::transform(section.el.clone(), Some(0.0), Some(-scroll_val), None);
@@ -703,8 +729,8 @@ impl SmoothScroll {
                     'y' => (instance.as_ref().borrow().scroll.y, instance.borrow().limit.y, core.as_ref().borrow().scroll_bar_limit.borrow().y),
                     _ => panic!()
                 };
-                let scroll_bar_translation = ( scroll_val / limit ) * scrollbar_limit;
-                if option.direction.as_str() == "horizontal" {
+                let scroll_bar_translation = engine::thumb_translation(scroll_val, limit, scrollbar_limit);
+                if option.direction == Direction::Horizontal {
                     SmoothScroll::transform(scroll.scrollbar_thumb.borrow().as_ref().unwrap().clone(), Some(scroll_bar_translation), Some(0.0), None);
                 } else {
                     SmoothScroll::transform(scroll.scrollbar_thumb.borrow().as_ref().unwrap().clone(), Some(0.0), Some(scroll_bar_translation), None);
@@ -728,7 +754,7 @@ impl SmoothScroll {
         {
             *core.borrow().has_scroll_bar.borrow_mut() = Some(false);
         }
-        if options.direction.as_str() == "horizontal" {
+        if options.direction == Direction::Horizontal {
             if limit.x + core.borrow().window_width <= core.borrow().window_width {
                 return;
             }
@@ -749,13 +775,13 @@ impl SmoothScroll {
             *scroll.scrollbar_width.borrow_mut() = Some(scrollbar_bcr.width());
         }
 
-        if options.direction.as_str() == "horizontal" {
+        if options.direction == Direction::Horizontal {
             let scrollbar_thumb = scroll.scrollbar_thumb.borrow();
             let html_scrollbar_thumb = scrollbar_thumb.as_ref().unwrap().dyn_ref::<HtmlElement>().unwrap();
             let style = html_scrollbar_thumb.style();
             let scrollbar_width = scroll.scrollbar_width.borrow();
             let scrollbar_width = scrollbar_width.as_ref().unwrap();
-            let width = format!("{:?}px", (scrollbar_width * scrollbar_width) / (limit.x + scrollbar_width));
+            let width = format!("{:?}px", engine::thumb_size(*scrollbar_width, limit.x));
             style.set_property("width", &width).unwrap();
         } else {
             let scrollbar_thumb = scroll.scrollbar_thumb.borrow();
@@ -763,8 +789,8 @@ impl SmoothScroll {
             let style = html_scrollbar_thumb.style();
             let scrollbar_height = scroll.scrollbar_height.borrow();
             let scrollbar_height = scrollbar_height.as_ref().unwrap();
-            let height = format!("{:?}px", (scrollbar_height * scrollbar_height) / (limit.y + scrollbar_height));
-            style.set_property("width", &height).unwrap();
+            let height = format!("{:?}px", engine::thumb_size(*scrollbar_height, limit.y));
+            style.set_property("height", &height).unwrap();
         }
 
         let scrollbar_thumb_bcr = scroll.scrollbar_thumb.borrow().as_ref().unwrap().get_bounding_client_rect();
@@ -783,27 +809,12 @@ impl SmoothScroll {
 
     pub fn add_direction(core: Rc<RefCell<Core>>) {
         let binding = core.as_ref().borrow();
+        let direction_axis = *binding.direction_axis.borrow();
         let mut instance = binding.instance.borrow_mut();
-        let (down, up, left, right) = (String::from("down"), String::from("up"), String::from("left"), String::from("right"));
-        
-        if instance.delta.as_ref().unwrap().y > instance.scroll.y {
-            if instance.direction != Some(down.clone()) {
-                instance.direction = Some(down);
-            } 
-        } else if instance.delta.as_ref().unwrap().y < instance.scroll.y {
-            if instance.direction != Some(up.clone()) {
-                instance.direction = Some(up);
-            }
-        }
+        let (scroll, delta) = (instance.scroll.get(direction_axis), instance.delta.as_ref().unwrap().get(direction_axis));
 
-        if instance.delta.as_ref().unwrap().x > instance.scroll.x {
-            if instance.direction != Some(right.clone()) {
-                instance.direction = Some(right);
-            } 
-        } else if instance.delta.as_ref().unwrap().x < instance.scroll.x {
-            if instance.direction != Some(left.clone()) {
-                instance.direction = Some(left);
-            }
+        if let Some(direction) = engine::direction(scroll, delta, direction_axis) {
+            instance.direction = Some(direction.to_string());
         }
     }
 
@@ -816,126 +827,37 @@ impl SmoothScroll {
             _ => panic!()
         };
 
-        if delta != scroll {
-            let val = (delta - scroll) / (f64::max(1.0, Date::now() - *speed_ts.as_ref().borrow().as_ref().unwrap()));
-            instance.speed = Some(val);
-        } else {
-            instance.speed = Some(0.0);
-        }
+        let elapsed = Date::now() - speed_ts.borrow().unwrap_or(0.0);
+        instance.speed = Some(engine::speed(scroll, delta, elapsed));
     }
 
     pub fn transform_elements(is_forced: Option<bool>, set_all_elements: Option<bool>, core: Rc<RefCell<Core>>, options: &LocomotiveOption) {
         let core = core.as_ref().borrow();
         let scroll = core.scroll.get_smooth();
-        let direction_axis = core.direction_axis.clone().as_ref().clone().into_inner();
-        let window_width = core.window_width;
-        let instance = core.instance.as_ref().borrow_mut();
-        let (scroll_val, limit, scroll_x, scroll_y) = match direction_axis {
-            'x' => (instance.scroll.x, instance.limit.x, instance.scroll.x, instance.scroll.y),
-            'y' => (instance.scroll.y, instance.limit.y, instance.scroll.x, instance.scroll.y),
-            _ => panic!("direction axis not supported")
-        };
+        let viewport = core.viewport();
         let parallax_elements = scroll.parallax_elements.clone();
 
-        let scroll_right = instance.scroll.x + window_width;
-        let scroll_bottom = instance.scroll.y + core.window_height;
-        let scroll_middle = Position::new(instance.scroll.x + core.window_middle.x, instance.scroll.y + core.window_middle.y);
-
         for (_id, parallax_elem) in parallax_elements.borrow().data.iter() {
             let current = parallax_elem.borrow();
-            let mut _transform_distance = None;
-            //let current = current.borrow_mut();
-
-            if is_forced.is_some() {
-                _transform_distance = Some(0.0);
-            }
-
-            if *current.in_view.as_ref().unwrap() || set_all_elements.is_some() {
-                let speed = current.speed.unwrap_or(0.0);
-                match current.position.as_ref().unwrap_or(&String::new()).as_str() {
-                    "top" => {
-                        _transform_distance = Some(scroll_val * (-speed));
-                    },
-                    "elementTop" => {
-                        _transform_distance = Some((scroll_bottom - current.top) * speed);
-                    },
-                    "bottom" => {
-                        _transform_distance = Some((limit - scroll_bottom + core.window_height) * speed);
-                    },
-                    "left" => {
-                        _transform_distance = Some(scroll_val * -speed);
-                    },
-                    "elementLeft" => {
-                        _transform_distance = Some((scroll_right - current.left) * -speed);
-                    },
-                    "right" => {
-                        _transform_distance = Some((limit - scroll_right + core.window_height) * speed);
-                    },
-                    _ => {
-                        let (current_middle, scroll_middle) = match direction_axis {
-                            'x' => (current.middle.as_ref().unwrap().x, scroll_middle.x),
-                            'y' => (current.middle.as_ref().unwrap().y, scroll_middle.y),
-                            _ => panic!()
-                        };
-
-                        _transform_distance = Some((scroll_middle - current_middle) * -speed);
-                    }
-                }
-            }
+            let rect = current.rect();
+            let parallax = Parallax {
+                rect,
+                middle: current.middle.clone().unwrap_or_else(|| rect.middle()),
+                speed: current.speed.unwrap_or(0.0),
+                position: current.position,
+                sticky: current.sticky.is_some(),
+                in_view: *current.in_view.as_ref().unwrap(),
+            };
 
-            if current.sticky.as_ref().is_some() {
-                let window = window().unwrap();
-                let inner_height = window.inner_height().unwrap().as_f64().unwrap();
-                let inner_width = window.inner_width().unwrap().as_f64().unwrap();
-                if *current.in_view.as_ref().unwrap() {
-                    if options.direction.as_str() == "horizontal" {
-                        _transform_distance = Some(scroll_x - current.left + inner_width);
-                    } else {
-                        _transform_distance = Some(scroll_y - current.top + inner_height);
-                    }
+            if let Some(val) = engine::parallax_distance(&parallax, &viewport, is_forced.is_some(), set_all_elements.is_some()) {
+                let delay = if is_forced.is_some() {
+                    current.delay.as_ref().and_then(|val| val.parse::<f64>().ok())
                 } else {
-                    if options.direction.as_str() == "horizontal" {
-                        if scroll_x < current.left - inner_width && scroll_x < current.left - inner_width / 2.0 {
-                            _transform_distance = Some(0.0)
-                        } else if scroll_x > current.right && scroll_x > current.right + 100.0 {
-                            _transform_distance = Some(current.right - current.left + inner_width);
-                        } else {
-                            _transform_distance = None;
-                        }
-                    } else {
-                        if scroll_y < current.top - inner_height && scroll_y < current.top - inner_height / 2.0 {
-                            _transform_distance = Some(0.0)
-                        } else if scroll_y > current.bottom && scroll_y > current.bottom + 100.0 {
-                            _transform_distance = Some(current.bottom - current.top + inner_height);
-                        } else {
-                            _transform_distance = None;
-                        } 
-                    }
-                }
-            }
-
-            if let Some(val) = _transform_distance {
-                if current.direction.as_ref().unwrap_or(&String::new()).as_str() == "horizontal" || (options.direction.as_str() == "horizontal" && current.direction.as_ref().unwrap_or(&String::new()).as_str() != "vertical") {
-                    let delay = if is_forced.is_some() {
-                        if let Some(val) = current.delay.as_ref() {
-                            Some(val.parse::<f64>().unwrap())
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    };
+                    None
+                };
+                if current.direction.unwrap_or(options.direction) == Direction::Horizontal {
                     SmoothScroll::transform(current.el.as_ref().unwrap().clone(), Some(val), Some(0.0), delay)
                 } else {
-                    let delay = if is_forced.is_some() {
-                        if let Some(val) = current.delay.as_ref() {
-                            Some(val.parse::<f64>().unwrap())
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    };
                     SmoothScroll::transform(current.el.as_ref().unwrap().clone(), Some(0.0), Some(val), delay)
                 }
             }
@@ -950,8 +872,6 @@ impl SmoothScroll {
             core.window_width = window().unwrap().inner_width().unwrap().as_f64().unwrap();
         }
 
-        Core::check_context(core);
-
         {   
             let window_height = core.window_height;
             let window_width = core.window_width;
@@ -962,13 +882,13 @@ impl SmoothScroll {
         }
     }
 
-    pub fn scroll_to(target: ScrollToTarget, scroll_to_option: ScrollToOption, core: Rc<RefCell<Core>>, options: &LocomotiveOption) {
+    pub fn scroll_to(target: ScrollToTarget, scroll_to_option: ScrollToOption, core: Rc<RefCell<Core>>, options: &LocomotiveOption) -> Promise {
         let core = core.clone();
         let direction_axis = core.borrow().direction_axis.clone().as_ref().clone().into_inner();
         let instance = core.borrow().instance.clone();
         let (limit, scroll_val, limit_x, limit_y) = (instance.borrow().limit.get(direction_axis), instance.borrow().scroll.get(direction_axis), instance.borrow().limit.x, instance.borrow().limit.y);
-        let scroll = &core.borrow().scroll;
-        let scroll = scroll.get_smooth();
+        //a clone, the core must not be borrowed while `start_scrolling`, `update` or the first tick run
+        let scroll = core.borrow().scroll.get_smooth().clone();
 
         
 
@@ -981,16 +901,18 @@ impl SmoothScroll {
             None => 1000.0,
         };
         let easing = match &scroll_to_option.easing {
-            Some(val) => *val,
-            None => [0.25, 0.0, 0.35, 1.0]
+            Some(val) => val.clone(),
+            None => options.easing.clone()
         };
         let disable_lerp = match &scroll_to_option.disable_lerp {
             Some(val) => *val,
             None => false
         };
-        //todo
-        //let callback = None;
-        let easing = bezier2(easing[0], easing[1], easing[2], easing[3]);
+        let callback = scroll_to_option.callback.clone();
+        let easing = match easing.into_fn() {
+            Ok(easing) => easing,
+            Err(_) => return ScrollToPromise::rejected(ScrollToInterrupt::InvalidEasing),
+        };
 
         let target = match &target {
             ScrollToTarget::String(val) => {
@@ -1004,7 +926,7 @@ impl SmoothScroll {
                         if let Some(el) = target {
                             ScrollToTarget::Element(el)
                         } else {
-                            return;
+                            return ScrollToPromise::rejected(ScrollToInterrupt::InvalidTarget);
                         }
                     }
                 }
@@ -1019,7 +941,7 @@ impl SmoothScroll {
                 let target_parents = get_parents(el.clone());
                 let target_in_scope = target_parents.contains(options.el.try_get_element().unwrap());
                 if !target_in_scope {
-                    return;
+                    return ScrollToPromise::rejected(ScrollToInterrupt::InvalidTarget);
                 }
 
                 let target_bcr = el.get_bounding_client_rect();
@@ -1034,7 +956,7 @@ impl SmoothScroll {
                     None => -scroll_val,
                 };
 
-                if options.direction.as_str() == "horizontal" {
+                if options.direction == Direction::Horizontal {
                     offset_left + offset - parent_section_offset
                 } else {
                     offset_top + offset - parent_section_offset
@@ -1046,15 +968,15 @@ impl SmoothScroll {
 
 
         //ACTUAL SCROLLTO
-        let scroll_start = limit;
-        let scroll_target = 0.0f64.max(offset.min(limit));
+        let scroll_start = instance.borrow().delta.as_ref().unwrap().get(direction_axis);
+        let scroll_target = engine::clamp(offset, limit);
         let scroll_diff = scroll_target - scroll_start;
-        let direction = options.direction.clone();
+        let direction = options.direction;
 
         let render = move |p: f64| {
             let instance = instance.clone();
             if disable_lerp {
-                if direction.as_str() == "horizontal" {
+                if direction == Direction::Horizontal {
                     SmoothScroll::set_scroll(instance.clone(), scroll_start + scroll_diff * p, instance.borrow().delta.as_ref().unwrap().y);
                 } else {
                     SmoothScroll::set_scroll(instance.clone(), instance.borrow().delta.as_ref().unwrap().x, scroll_start + scroll_diff * p);
@@ -1069,6 +991,41 @@ impl SmoothScroll {
         };
         let render = Box::new(render);
 
+        //a previous `scrollTo` ends here, its pending frame would tick the new loop once more
+        if let Some(handler) = scroll.scroll_to_raf.borrow_mut().take() {
+            window().unwrap().cancel_animation_frame(handler).unwrap();
+        }
+        let (promise, settle) = ScrollToPromise::new();
+        {
+            let previous = scroll.scroll_to_promise.borrow_mut().replace(settle);
+            if let Some(previous) = previous {
+                previous.reject(ScrollToInterrupt::Cancelled);
+            }
+        }
+
+        //nothing to animate, the loop would divide by a zero duration
+        if duration <= 0.0 {
+            {
+                *scroll.animating_scroll.borrow_mut() = false;
+            }
+            let instance = core.borrow().instance.clone();
+            let mut position = instance.borrow().delta.clone().unwrap();
+            position.set(scroll_target, direction_axis);
+            SmoothScroll::set_scroll(instance.clone(), position.x, position.y);
+            if let Err(err) = SmoothScroll::update(core.clone(), options, &UpdateOption::default()) {
+                console::error_1(&err.to_js());
+            }
+
+            if let Some(callback) = callback.as_ref() {
+                callback();
+            }
+            let settle = scroll.scroll_to_promise.borrow_mut().take();
+            if let Some(settle) = settle {
+                settle.resolve(&position);
+            }
+            return promise;
+        }
+
         {
             *scroll.animating_scroll.borrow_mut() = true;
         }
@@ -1079,27 +1036,30 @@ impl SmoothScroll {
             SmoothScroll::start_scrolling(core.clone(), options.clone());
         }
 
-        let core_1 = core.clone();
-        let options_1 = options.clone();
+        let animating_scroll = scroll.animating_scroll.clone();
+        let scroll_to_raf = scroll.scroll_to_raf.clone();
+        let loop_cb = scroll.loop_cb.clone();
+        let scroll_to_promise = scroll.scroll_to_promise.clone();
+        let instance = core.borrow().instance.clone();
         let start = Date::now();
         let loop_event = Closure::wrap(Box::new(move || {
 
             let p = ( Date::now() - start ) / duration;
-            let options = options_1.clone();
-            let core = core_1.clone();
-            let scroll = &core.borrow().scroll;
-            let scroll = scroll.get_smooth();
 
             if p > 1.0 {
                 render(1.0);
-                *scroll.animating_scroll.clone().borrow_mut() = false;
+                *animating_scroll.borrow_mut() = false;
 
-                if duration == 0.0 {
-                    SmoothScroll::update(core.clone(), &options);
+                if let Some(callback) = callback.as_ref() {
+                    callback();
+                }
+
+                let settle = scroll_to_promise.borrow_mut().take();
+                if let Some(settle) = settle {
+                    settle.resolve(instance.borrow().delta.as_ref().unwrap());
                 }
-            
             } else {
-               *scroll.scroll_to_raf.borrow_mut() = Some(window().unwrap().request_animation_frame(scroll.loop_cb.borrow().as_ref().unwrap().as_ref().unchecked_ref()).unwrap());
+               *scroll_to_raf.borrow_mut() = Some(window().unwrap().request_animation_frame(loop_cb.borrow().as_ref().unwrap().as_ref().unchecked_ref()).unwrap());
                render(easing(p));
             }
         }) as Box<dyn Fn()>);
@@ -1108,18 +1068,239 @@ impl SmoothScroll {
         }
         scroll.loop_cb.borrow().as_ref().unwrap().as_ref().unchecked_ref::<Function>().call0(&"".into()).unwrap();
 
+        promise
+    }
+
+    /// Freeze the scroll where it currently is, until `start_scroll` is called.
+    pub fn stop_scroll(core: Rc<RefCell<Core>>, options: &LocomotiveOption) {
+        let core_ref = core.borrow();
+        let scroll = core_ref.scroll.get_smooth();
+        let instance = core_ref.instance.clone();
+
+        {
+            *scroll.stop.borrow_mut() = true;
+        }
+        SmoothScroll::interrupt_scroll_to(scroll.animating_scroll.clone(), scroll.scroll_to_raf.clone(), scroll.scroll_to_promise.clone(), ScrollToInterrupt::Stopped);
+        SmoothScroll::stop_scrolling(core.clone(), scroll.start_scroll_ts.clone(), scroll.check_scroll_raf.clone(), scroll.scroll_to_raf.clone(), scroll.is_scrolling.clone(), options.scrolling_class.clone());
+        {
+            *scroll.check_scroll_raf.borrow_mut() = None;
+        }
+
+        //keep the delta on the frozen position, otherwise the scroll jumps on `start_scroll`
+        let mut instance = instance.borrow_mut();
+        let scroll_pos = instance.scroll.clone();
+        instance.set_delta(scroll_pos);
+    }
+
+    /// Undo everything `init` did: listeners, pending frames, scrollbar and transforms.
+    /// The closures are dropped last since they hold a reference to the core.
+    pub fn destroy(core: Rc<RefCell<Core>>, options: &LocomotiveOption) {
+        let core_ref = core.borrow();
+        let scroll = core_ref.scroll.get_smooth();
+        let window = window().unwrap();
+
+        SmoothScroll::interrupt_scroll_to(scroll.animating_scroll.clone(), scroll.scroll_to_raf.clone(), scroll.scroll_to_promise.clone(), ScrollToInterrupt::Destroyed);
+        SmoothScroll::stop_scrolling(core.clone(), scroll.start_scroll_ts.clone(), scroll.check_scroll_raf.clone(), scroll.scroll_to_raf.clone(), scroll.is_scrolling.clone(), options.scrolling_class.clone());
+        for raf in [&scroll.check_scroll_raf, &scroll.check_key_raf, &scroll.focus_raf, &scroll.vs_raf, &scroll.move_scrollbar_raf] {
+            if let Some(handler) = raf.borrow_mut().take() {
+                let _ = window.cancel_animation_frame(handler);
+            }
+        }
+
+        if let Some(virtual_scroll) = scroll.virtual_scroll.as_ref() {
+            virtual_scroll.destroy();
+        }
+        if let Some(cb) = scroll.check_key.borrow().as_ref() {
+            let _ = window.remove_event_listener_with_callback("keydown", cb.as_ref().unchecked_ref());
+        }
+        if let Some(cb) = scroll.check_focus.borrow().as_ref() {
+            let _ = window.remove_event_listener_with_callback("focusin", cb.as_ref().unchecked_ref());
+        }
 
+        //scrollbar
+        if let (Some(thumb), Some(cb)) = (scroll.scrollbar_thumb.borrow().as_ref(), scroll.get_scrollbar.borrow().as_ref()) {
+            let _ = thumb.remove_event_listener_with_callback("mousedown", cb.as_ref().unchecked_ref());
+        }
+        if let Some(cb) = scroll.release_scrollbar.borrow().as_ref() {
+            let _ = window.remove_event_listener_with_callback("mouseup", cb.as_ref().unchecked_ref());
+        }
+        if let Some(cb) = scroll.move_scrollbar.borrow().as_ref() {
+            let _ = window.remove_event_listener_with_callback("mousemove", cb.as_ref().unchecked_ref());
+        }
+        if let Some(scrollbar) = scroll.scrollbar.borrow_mut().take() {
+            scrollbar.remove();
+        }
+        {
+            *scroll.scrollbar_thumb.borrow_mut() = None;
+        }
+
+        //sections and parallax elements
+        for section in scroll.sections.borrow().data.values() {
+            let section = section.borrow();
+            SmoothScroll::reset_transform(&section.el);
+            let style = section.el.dyn_ref::<HtmlElement>().unwrap().style();
+            let _ = style.remove_property("opacity");
+            let _ = style.remove_property("pointer-events");
+            let _ = section.el.remove_attribute(&options.names.as_ref().unwrap().data_section_inview);
+        }
+        for el in scroll.parallax_elements.borrow().data.values() {
+            if let Some(el) = el.borrow().el.as_ref() {
+                SmoothScroll::reset_transform(el);
+            }
+        }
+
+        //closures
+        scroll.check_key.borrow_mut().take();
+        scroll.check_focus.borrow_mut().take();
+        scroll.get_scrollbar.borrow_mut().take();
+        scroll.release_scrollbar.borrow_mut().take();
+        scroll.move_scrollbar.borrow_mut().take();
+        scroll.check_key_cb_1.borrow_mut().take();
+        scroll.check_key_cb_2.borrow_mut().take();
+        scroll.vs_cb_1.borrow_mut().take();
+        scroll.vs_cb_2.borrow_mut().take();
+        scroll.check_scroll_cb.borrow_mut().take();
+        scroll.move_scrollbar_cb_2.borrow_mut().take();
+        scroll.loop_cb.borrow_mut().take();
     }
 
-    pub(crate) fn update(core: Rc<RefCell<Core>>, options: &LocomotiveOption) {
+    pub fn start_scroll(core: Rc<RefCell<Core>>) {
+        let core_ref = core.borrow();
+        let scroll = core_ref.scroll.get_smooth();
+
+        *scroll.stop.borrow_mut() = false;
+    }
+
+    /// Cancel a running `scrollTo` animation and reject its promise with `reason`.
+    pub fn interrupt_scroll_to(animating_scroll: Rc<RefCell<bool>>, scroll_to_raf: Rc<RefCell<Option<i32>>>,
+        scroll_to_promise: Rc<RefCell<Option<ScrollToPromise>>>, reason: ScrollToInterrupt
+    ) {
+        if !*animating_scroll.borrow() {
+            return;
+        }
+
+        {
+            *animating_scroll.borrow_mut() = false;
+        }
+        if let Some(handler) = scroll_to_raf.borrow_mut().take() {
+            window().unwrap().cancel_animation_frame(handler).unwrap();
+        }
+
+        let settle = scroll_to_promise.borrow_mut().take();
+        if let Some(settle) = settle {
+            settle.reject(reason);
+        }
+    }
+
+    /// Scroll with the navigation keys, the same way the wheel does: by moving the delta.
+    pub fn check_key(event: KeyboardEvent, core: Rc<RefCell<Core>>, options: &LocomotiveOption) {
+        if event.default_prevented() || event.ctrl_key() || event.alt_key() || event.meta_key() {
+            return;
+        }
+        if SmoothScroll::is_typing(event.key_code()) {
+            return;
+        }
+
+        let (scroll, instance, direction_axis, window_size) = {
+            let core_ref = core.borrow();
+            let direction_axis = *core_ref.direction_axis.borrow();
+            let window_size = match direction_axis {
+                'x' => core_ref.window_width,
+                _ => core_ref.window_height
+            };
+            (core_ref.scroll.get_smooth().clone(), core_ref.instance.clone(), direction_axis, window_size)
+        };
+        let limit = instance.borrow().limit.get(direction_axis);
+        let page_step = options.page_step.unwrap_or(window_size);
+
+        let delta = instance.borrow().delta.as_ref().unwrap().get(direction_axis);
+        let target = match engine::key_target(event.key_code(), event.shift_key(), direction_axis, delta, options.key_step, page_step, limit) {
+            Some(target) => target,
+            None => return
+        };
+        event.prevent_default();
+
+        SmoothScroll::interrupt_scroll_to(scroll.animating_scroll.clone(), scroll.scroll_to_raf.clone(), scroll.scroll_to_promise.clone(), ScrollToInterrupt::Keyboard);
+        {
+            let mut instance = instance.borrow_mut();
+            instance.delta.as_mut().unwrap().set(target, direction_axis);
+        }
+
+        if !*scroll.is_scrolling.borrow() {
+            SmoothScroll::start_scrolling(core, options.clone());
+        }
+    }
+
+    /// Scroll just enough for `el` to be fully visible, `focus_margin` included.
+    /// The browser can't do it itself since the content is moved by transforms.
+    pub fn scroll_into_view(el: &Element, core: Rc<RefCell<Core>>, options: &LocomotiveOption) {
+        let (scroll, instance, direction_axis, window_size) = {
+            let core_ref = core.borrow();
+            let direction_axis = *core_ref.direction_axis.borrow();
+            let window_size = match direction_axis {
+                'x' => core_ref.window_width,
+                _ => core_ref.window_height
+            };
+            (core_ref.scroll.clone(), core_ref.instance.clone(), direction_axis, window_size)
+        };
+        let scroll_pos = instance.borrow().scroll.get(direction_axis);
+
+        //position on the page, through the translation of the section holding the element
+        let el_bcr = el.get_bounding_client_rect();
+        let (el_start, el_size) = match direction_axis {
+            'x' => (el_bcr.left(), el_bcr.width()),
+            _ => (el_bcr.top(), el_bcr.height())
+        };
+        let parents = get_parents(el.clone());
+        let section_translate = scroll.get_smooth().sections.borrow()
+            .data
+            .values()
+            .find(|section| parents.contains(&section.borrow().el))
+            .map(|section| get_translate(&section.borrow().el).get(direction_axis));
+        let start = match section_translate {
+            Some(translate) => el_start - translate,
+            None => el_start + scroll_pos
+        };
+        let end = start + el_size;
+
+        let target = if start - options.focus_margin < scroll_pos || el_size + options.focus_margin * 2.0 > window_size {
+            start - options.focus_margin
+        } else if end + options.focus_margin > scroll_pos + window_size {
+            end + options.focus_margin - window_size
+        } else {
+            return;
+        };
+
+        let _ = Core::scroll_to(scroll, ScrollToTarget::Num(target), ScrollToOption::default(), core, options);
+    }
+
+    /// Keys typed in a form field or an editable element are left alone,
+    /// so is space on a button, which clicks it.
+    fn is_typing(key: u32) -> bool {
+        let active_element = match window().unwrap().document().unwrap().active_element() {
+            Some(el) => el,
+            None => return false
+        };
+
+        match active_element.tag_name().as_str() {
+            "INPUT" | "TEXTAREA" | "SELECT" => true,
+            "BUTTON" => key == SPACE,
+            _ => active_element.dyn_ref::<HtmlElement>().is_some_and(|el| el.is_content_editable())
+        }
+    }
+
+    pub fn update(core: Rc<RefCell<Core>>, options: &LocomotiveOption, update_option: &UpdateOption) -> Result<(), LocomotiveError> {
         SmoothScroll::set_scroll_limit(core.clone(), options);
         SmoothScroll::add_sections(core.clone(), options);
-        SmoothScroll::add_elements(core.clone(), options);
+        SmoothScroll::add_elements(core.clone(), options)?;
         Core::detect_elements(None, core.clone(), options);
-        SmoothScroll::update_scroll(core.clone(), 0.0);
+        if !update_option.preserve_scroll {
+            SmoothScroll::clamp_scroll(core.clone());
+        }
         SmoothScroll::transform_elements(Some(true), None, core.clone(), options);
         SmoothScroll::reinit_scrollbar(core.clone(), options);
 
-        SmoothScroll::check_scroll(Some(true), core.clone(), options.clone());
+        SmoothScroll::check_scroll(Some(true), None, core.clone(), options.clone());
+        Ok(())
     }
 }
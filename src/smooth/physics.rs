@@ -0,0 +1,240 @@
+use crate::{option::{Physics, Spring}, utils::lerp};
+
+/// Duration of the frame `lerp` and `friction` are given for, in ms.
+pub const FRAME: f64 = 1000.0 / 60.0;
+/// Longer frames are cut down, so a throttled tab does not jump when it comes back.
+const MAX_DT: f64 = 64.0;
+const SPRING_SUBSTEP: f64 = 1000.0 / 120.0;
+/// Coasting stops below this velocity, in px/ms.
+const MIN_VELOCITY: f64 = 0.01;
+
+/// `lerp_amt` is given for a 60Hz frame, this is the amount covering the same share of the distance in `dt` ms.
+pub fn frame_lerp(lerp_amt: f64, dt: f64) -> f64 {
+    1.0 - (1.0 - lerp_amt).powf(dt / FRAME)
+}
+
+/// State the physics carry from one frame to the next.
+#[derive(Clone, Debug, Default)]
+pub struct Integrator {
+    /// In px/ms.
+    velocity: f64,
+    last_target: Option<f64>,
+    last_ts: Option<f64>,
+}
+
+impl Integrator {
+    pub fn reset(&mut self) {
+        *self = Integrator::default();
+    }
+
+    /// A spring still oscillating or a target still coasting.
+    pub fn is_moving(&self) -> bool {
+        self.velocity.abs() >= MIN_VELOCITY
+    }
+
+    /// Time since the previous frame, one frame for the first one.
+    pub fn frame_dt(&mut self, now: f64) -> f64 {
+        let dt = self.last_ts.map_or(FRAME, |last_ts| now - last_ts);
+        self.last_ts = Some(now);
+        dt
+    }
+
+    /// Move `scroll` towards `target` for `dt` ms and return the new scroll and target.
+    /// Only `Physics::Friction` moves the target, while it coasts.
+    pub fn step(&mut self, physics: &Physics, lerp_amt: f64, dt: f64, scroll: f64, target: f64, limit: f64) -> (f64, f64) {
+        let dt = dt.clamp(0.0, MAX_DT);
+        match physics {
+            Physics::Lerp => {
+                self.velocity = 0.0;
+                self.last_target = None;
+                (lerp(scroll, target, frame_lerp(lerp_amt, dt)), target)
+            },
+            Physics::Spring(spring) => (self.spring(spring, dt, scroll, target), target),
+            Physics::Friction(friction) => {
+                let target = self.coast(friction.friction, dt, target, limit);
+                (lerp(scroll, target, frame_lerp(lerp_amt, dt)), target)
+            },
+        }
+    }
+
+    /// Semi-implicit Euler, in substeps so a long frame stays stable.
+    fn spring(&mut self, spring: &Spring, dt: f64, scroll: f64, target: f64) -> f64 {
+        let damping = spring.damping.unwrap_or_else(|| 2.0 * (spring.stiffness * spring.mass).sqrt());
+        let substeps = (dt / SPRING_SUBSTEP).ceil().max(1.0);
+        let h = dt / substeps / 1000.0;
+
+        let mut position = scroll;
+        let mut velocity = self.velocity * 1000.0;
+        for _ in 0..substeps as u32 {
+            let acceleration = (spring.stiffness * (target - position) - damping * velocity) / spring.mass;
+            velocity += acceleration * h;
+            position += velocity * h;
+        }
+
+        self.velocity = velocity / 1000.0;
+        position
+    }
+
+    /// While the target moves, remember how fast. Once it stops, keep it going at that speed minus the friction.
+    fn coast(&mut self, friction: f64, dt: f64, target: f64, limit: f64) -> f64 {
+        let moved = self.last_target.map_or(0.0, |last_target| target - last_target);
+
+        let target = if moved != 0.0 && dt > 0.0 {
+            self.velocity = moved / dt;
+            target
+        } else {
+            self.velocity *= friction.powf(dt / FRAME);
+            if self.velocity.abs() < MIN_VELOCITY {
+                self.velocity = 0.0;
+            }
+
+            let coasted = (target + self.velocity * dt).clamp(0.0, limit);
+            if coasted == 0.0 || coasted == limit {
+                self.velocity = 0.0;
+            }
+            coasted
+        };
+
+        self.last_target = Some(target);
+        target
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::option::Friction;
+
+    const LIMIT: f64 = 10000.0;
+
+    fn run(integrator: &mut Integrator, physics: &Physics, frames: u32, mut scroll: f64, mut target: f64) -> (f64, f64) {
+        for _ in 0..frames {
+            (scroll, target) = integrator.step(physics, 0.1, FRAME, scroll, target, LIMIT);
+        }
+        (scroll, target)
+    }
+
+    #[test]
+    fn frame_dt_starts_with_a_frame() {
+        let mut integrator = Integrator::default();
+        assert_eq!(integrator.frame_dt(1000.0), FRAME);
+        assert_eq!(integrator.frame_dt(1020.0), 20.0);
+        integrator.reset();
+        assert_eq!(integrator.frame_dt(2000.0), FRAME);
+    }
+
+    #[test]
+    fn lerp_moves_by_the_lerp_amount() {
+        let mut integrator = Integrator::default();
+        let (scroll, target) = integrator.step(&Physics::Lerp, 0.1, FRAME, 0.0, 100.0, LIMIT);
+        assert!((scroll - 10.0).abs() < 1e-9);
+        assert_eq!(target, 100.0);
+
+        let (scroll, target) = run(&mut integrator, &Physics::Lerp, 200, 0.0, 100.0);
+        assert!((scroll - 100.0).abs() < 0.01);
+        assert_eq!(target, 100.0);
+    }
+
+    #[test]
+    fn frame_lerp_is_frame_rate_independent() {
+        assert!((frame_lerp(0.1, FRAME) - 0.1).abs() < 1e-12);
+        assert_eq!(frame_lerp(0.1, 0.0), 0.0);
+
+        //Two 120Hz frames cover as much as one 60Hz frame
+        let mut integrator = Integrator::default();
+        let (half, _) = integrator.step(&Physics::Lerp, 0.1, FRAME / 2.0, 0.0, 100.0, LIMIT);
+        let (full, _) = integrator.step(&Physics::Lerp, 0.1, FRAME / 2.0, half, 100.0, LIMIT);
+        assert!((full - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn long_frames_are_capped() {
+        let mut integrator = Integrator::default();
+        let (capped, _) = integrator.step(&Physics::Lerp, 0.1, 10_000.0, 0.0, 100.0, LIMIT);
+        assert!((capped - 100.0 * frame_lerp(0.1, MAX_DT)).abs() < 1e-9);
+        assert!(capped < 100.0);
+    }
+
+    #[test]
+    fn critically_damped_spring_does_not_overshoot() {
+        let physics = Physics::Spring(Spring::default());
+        let mut integrator = Integrator::default();
+        let mut scroll = 0.0;
+        for _ in 0..120 {
+            (scroll, _) = integrator.step(&physics, 0.1, FRAME, scroll, 1000.0, LIMIT);
+            assert!(scroll <= 1000.0);
+        }
+        assert!((scroll - 1000.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn underdamped_spring_overshoots_then_settles() {
+        let physics = Physics::Spring(Spring { stiffness: 170.0, damping: Some(5.0), mass: 1.0 });
+        let mut integrator = Integrator::default();
+        let mut scroll = 0.0;
+        let mut max_scroll: f64 = 0.0;
+        for _ in 0..600 {
+            (scroll, _) = integrator.step(&physics, 0.1, FRAME, scroll, 1000.0, LIMIT);
+            max_scroll = max_scroll.max(scroll);
+        }
+        assert!(max_scroll > 1000.0);
+        assert!((scroll - 1000.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn spring_is_stable_on_long_frames() {
+        let physics = Physics::Spring(Spring::default());
+        let mut integrator = Integrator::default();
+        let mut scroll = 0.0;
+        for _ in 0..30 {
+            (scroll, _) = integrator.step(&physics, 0.1, 500.0, scroll, 1000.0, LIMIT);
+            assert!(scroll.is_finite() && scroll <= 1000.0);
+        }
+        assert!((scroll - 1000.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn friction_coasts_past_the_last_input() {
+        let physics = Physics::Friction(Friction::default());
+        let mut integrator = Integrator::default();
+        let (mut scroll, mut target) = (0.0, 0.0);
+
+        //A flick, 50px a frame for 5 frames
+        for _ in 0..5 {
+            target += 50.0;
+            (scroll, target) = integrator.step(&physics, 0.1, FRAME, scroll, target, LIMIT);
+        }
+        assert_eq!(target, 250.0);
+
+        (scroll, target) = run(&mut integrator, &physics, 300, scroll, target);
+        //50 * 0.9 / (1 - 0.9) more, give or take the velocity cut-off
+        assert!(target > 650.0 && target <= 700.0, "target {target}");
+        assert!((scroll - target).abs() < 0.5);
+        assert_eq!(integrator.velocity, 0.0);
+    }
+
+    #[test]
+    fn friction_stops_at_the_limit() {
+        let physics = Physics::Friction(Friction::default());
+        let mut integrator = Integrator::default();
+        integrator.step(&physics, 0.1, FRAME, 0.0, LIMIT - 500.0, LIMIT);
+        integrator.step(&physics, 0.1, FRAME, 0.0, LIMIT - 100.0, LIMIT);
+
+        let (_, target) = run(&mut integrator, &physics, 300, 0.0, LIMIT - 100.0);
+        assert_eq!(target, LIMIT);
+        assert_eq!(integrator.velocity, 0.0);
+    }
+
+    #[test]
+    fn switching_to_lerp_drops_the_velocity() {
+        let physics = Physics::Friction(Friction::default());
+        let mut integrator = Integrator::default();
+        integrator.step(&physics, 0.1, FRAME, 0.0, 0.0, LIMIT);
+        integrator.step(&physics, 0.1, FRAME, 0.0, 100.0, LIMIT);
+
+        integrator.step(&Physics::Lerp, 0.1, FRAME, 0.0, 100.0, LIMIT);
+        let (_, target) = integrator.step(&physics, 0.1, FRAME, 0.0, 100.0, LIMIT);
+        assert_eq!(target, 100.0);
+    }
+}
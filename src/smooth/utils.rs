@@ -1,30 +1,19 @@
 use std::{rc::Rc, cell::RefCell, collections::HashMap};
 
 use convert_js::{ToJs, __internal::JsObject};
-use js_sys::Date;
 use wasm_bindgen::{JsCast, JsValue};
-use web_sys::{WheelEvent, Element, HtmlElement};
+use web_sys::{Element, HtmlElement};
 
-use crate::{core::Core, option::{LocomotiveOption, Position}, utils::{get_translate, lerp, els::MappedEl}};
+use crate::{core::Core, engine, option::{LocomotiveOption, Position, GestureDirection}, utils::{get_translate, lerp, now, els::MappedEl, listeners::ListenerEvent}, virtual_scroll::VsEvent};
 
 use super::SmoothScroll;
 
-const EL: &'static str = "el";
-const IN_VIEW: &'static str = "inView";
-const POSITION: &'static str = "position";
-const SPEED: &'static str = "speed";
-const TOP: &'static str = "top";
-const BOTTOM: &'static str = "bottom";
-const LEFT: &'static str = "left";
-const RIGHT: &'static str = "right";
-const MIDDLE: &'static str = "middle";
-const STICKY: &'static str = "sticky";
-const DIRECTION: &'static str = "direction";
-const DELAY: &'static str = "delay";
-const PERSISTENT: &'static str = "persistent";
-const OFFSET: &'static str = "offset";
-const LIMIT: &'static str = "limit";
-const ID: &'static str = "id";
+const EL: &str = "el";
+const IN_VIEW: &str = "inView";
+const PERSISTENT: &str = "persistent";
+const OFFSET: &str = "offset";
+const LIMIT: &str = "limit";
+const ID: &str = "id";
 
 
 #[derive(Debug, Clone)]
@@ -52,44 +41,6 @@ impl ToJs for Section {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct ParallaxElement {
-    pub el: Element,
-    pub in_view: bool,
-    pub position: String,
-    pub speed: Option<f64>,
-    pub top: f64,
-    pub bottom: f64,
-    pub left: f64,
-    pub right: f64,
-    pub middle: Position,
-    pub sticky: bool,
-    pub direction: String,
-    pub delay: Option<f64>,
-}
-
-impl ToJs for ParallaxElement {
-    fn to_js(&self) -> JsValue {
-        let jsobject = JsObject::new();
-
-        jsobject.set_prop(&EL, self.el.dyn_ref::<JsValue>().unwrap());
-        jsobject.set_prop(&IN_VIEW, &self.in_view);
-        jsobject.set_prop(&POSITION, &self.position);
-        jsobject.set_prop(&SPEED, &self.speed);
-        jsobject.set_prop(&TOP, &self.top);
-        jsobject.set_prop(&BOTTOM, &self.bottom);
-        jsobject.set_prop(&LEFT, &self.left);
-        jsobject.set_prop(&RIGHT, &self.right);
-        jsobject.set_prop(&MIDDLE, &self.middle);
-        jsobject.set_prop(&STICKY, &self.sticky);
-        jsobject.set_prop(&DIRECTION, &self.direction);
-        jsobject.set_prop(&DELAY, &self.delay);
-
-        jsobject.into_js_value()
-    }
-}
-
-
 #[derive(Debug, Clone)]
 pub struct Sections {
     pub data: HashMap<String, Rc<RefCell<Section>>>,
@@ -154,35 +105,26 @@ impl ToJs for ParallaxElements {
 
 
 
-pub fn get_gesture_direction(core: Rc<RefCell<Core>>, options: &LocomotiveOption) -> String {
+pub fn get_gesture_direction(core: Rc<RefCell<Core>>, options: &LocomotiveOption) -> GestureDirection {
     let ref_core = core.as_ref().borrow();
-    let ref_context = ref_core.context.as_ref().borrow();
-    let context = ref_context.as_str();
-    match context {
-        "desktop" => {
-            options.gesture_direction.clone()
-        },
-        "smartphone" => {
-            options.smartphone.as_ref().unwrap().gesture_direction.clone()
-        },
-        "tablet" => {
-            options.tablet.as_ref().unwrap().gesture_direction.clone()
-        },
-        _ => panic!("device context not supported")
+    let context = *ref_core.context.borrow();
+    //checked when the instance got built
+    match options.context_option(context) {
+        Ok(context) => context.gesture_direction,
+        Err(_) => options.gesture_direction,
     }
 
 
 }
 
 impl SmoothScroll {
-    pub fn update_delta(event: WheelEvent, core: Rc<RefCell<Core>>, options: LocomotiveOption) {
+    pub fn update_delta(event: &VsEvent, core: Rc<RefCell<Core>>, options: LocomotiveOption) {
         let gesture_direction = get_gesture_direction(core.clone(), &options);
 
-        let delta = match gesture_direction.as_str() {
-            "both" => event.delta_x() + event.delta_y(),
-            "vertical" => event.delta_y(),
-            "horizontal" => event.delta_x(),
-            _ => panic!("gesture direction not supported")
+        let delta = match gesture_direction {
+            GestureDirection::Both => event.delta_x + event.delta_y,
+            GestureDirection::Vertical => event.delta_y,
+            GestureDirection::Horizontal => event.delta_x,
         };
 
         {
@@ -191,29 +133,9 @@ impl SmoothScroll {
             let direction_axis = core.direction_axis.as_ref().borrow();
             let limit = instance.limit.clone();
             
-            match *direction_axis {
-                'x' => {
-                    let delta_ins = instance.delta.as_mut().unwrap();
-                    delta_ins.x -= delta * options.multiplier;
-                    if delta_ins.x < 0.0 {
-                        delta_ins.x = 0.0
-                    }
-                    if delta_ins.x > limit.x {
-                        delta_ins.x = limit.x;
-                    }
-                },
-                'y' => {
-                    let delta_ins = instance.delta.as_mut().unwrap();
-                    delta_ins.y -= delta * options.multiplier;
-                    if delta_ins.y < 0.0 {
-                        delta_ins.y = 0.0
-                    }
-                    if delta_ins.y > limit.y {
-                        delta_ins.y = limit.y;
-                    }
-                },
-                _ => panic!("direction axis not supported"),
-            }
+            let delta_ins = instance.delta.as_mut().unwrap();
+            let new_delta = engine::clamp(delta_ins.get(*direction_axis) - delta * options.multiplier, limit.get(*direction_axis));
+            delta_ins.set(new_delta, *direction_axis);
             
         }
 
@@ -221,20 +143,20 @@ impl SmoothScroll {
     }
 
     pub fn start_scrolling(core: Rc<RefCell<Core>>, options: LocomotiveOption) {
-        let core_ref = core.borrow();
-        let scroll = core_ref.scroll.get_smooth();
-
         {
-            *scroll.start_scroll_ts.clone().borrow_mut() = Some(Date::now());
+            let core_ref = core.borrow();
+            let scroll = core_ref.scroll.get_smooth();
+            *scroll.start_scroll_ts.clone().borrow_mut() = Some(now());
             *scroll.is_scrolling.clone().borrow_mut() = true;
         }
         {
-            SmoothScroll::check_scroll(None, core.clone(), options.clone());
+            SmoothScroll::check_scroll(None, None, core.clone(), options.clone());
         }
         {
-            core_ref.html.borrow().class_list().add_1(&options.scrolling_class).unwrap();
+            core.borrow().html.borrow().class_list().add_1(&options.scrolling_class).unwrap();
         }
 
+        Core::emit(core.clone(), ListenerEvent::ScrollStart);
     }
 
     pub fn transform(el: Element, x: Option<f64>, y: Option<f64>, delay: Option<f64>) {
@@ -259,4 +181,11 @@ impl SmoothScroll {
         //let js_val = el.dyn_ref::<JsValue>().unwrap();
         //web_sys::console::log_1(js_val);
     }
+
+    pub fn reset_transform(el: &Element) {
+        let style = el.dyn_ref::<HtmlElement>().unwrap().style();
+        let _ = style.remove_property("webkitTransform");
+        let _ = style.remove_property("msTransform");
+        let _ = style.remove_property("transform");
+    }
 }
\ No newline at end of file
@@ -1,5 +1,8 @@
 use std::collections::HashMap;
 
+use convert_js::{ToJs, __internal::JsObject};
+use wasm_bindgen::JsValue;
+
 use super::els::MappedEl;
 
 #[derive(Clone, Debug)]
@@ -12,4 +15,16 @@ impl CurrentElements {
     pub fn new() -> Self {
         CurrentElements { data: HashMap::new() }
     }
-}
\ No newline at end of file
+}
+
+impl ToJs for CurrentElements {
+    fn to_js(&self) -> JsValue {
+        let jsobject = JsObject::new();
+
+        for (key, val) in self.data.iter() {
+            jsobject.set_prop(key, &val.to_js_value());
+        }
+
+        jsobject.into_js_value()
+    }
+}
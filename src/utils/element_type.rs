@@ -1,6 +1,6 @@
 use convert_js::ToJs;
 use wasm_bindgen::{JsValue, JsCast};
-use web_sys::{window, Document, Element, NodeList, Event, HtmlCollection};
+use web_sys::{window, Document, Element, NodeList, Event, EventTarget, HtmlCollection};
 
 #[derive(Clone, Debug)]
 pub enum ElementType {
@@ -58,6 +58,13 @@ impl ElementType {
         }
     }
 
+    pub fn event_target(&self) -> EventTarget {
+        match self {
+            ElementType::Document(doc) => doc.clone().into(),
+            ElementType::Element(el) => el.clone().into()
+        }
+    }
+
     pub fn dispatch_event(&self, event: &Event) {
         match self {
             ElementType::Document(doc) => doc.dispatch_event(event).unwrap(),
@@ -69,13 +76,6 @@ impl ElementType {
         Self::Element(element)
     }
 
-    pub fn to_js(self) -> JsValue {
-        match self {
-            ElementType::Document(doc) => doc.dyn_into::<JsValue>().unwrap(),
-            ElementType::Element(el) => el.dyn_into::<JsValue>().unwrap()
-        }
-    }
-
     pub fn children(&self) -> HtmlCollection {
         match self {
             ElementType::Document(doc) => doc.children(),
@@ -1,10 +1,12 @@
 use std::{collections::HashMap, rc::Rc, cell::RefCell};
 
 use convert_js::{__internal::JsObject, ToJs};
+use js_sys::{Function, Reflect, Promise, Error};
+use serde::Deserialize;
 use wasm_bindgen::{JsCast, JsValue};
-use web_sys::Element;
+use web_sys::{Element, console};
 
-use crate::{smooth::Section, option::Position};
+use crate::{smooth::Section, option::{Position, ParallaxPosition, Direction, OptionValue}, bezier_easing::Easing, engine::Rect};
 
 #[derive(Clone, Debug)]
 pub struct MappedEl {
@@ -25,11 +27,17 @@ pub struct MappedEl {
     pub section: Option<Rc<RefCell<Section>>>,
     pub speed: Option<f64>,
     pub delay: Option<String>,
-    pub position: Option<String>,
-    pub direction: Option<String>,
+    pub position: Option<ParallaxPosition>,
+    pub direction: Option<Direction>,
     pub sticky: Option<String>,
 }
 
+impl MappedEl {
+    pub fn rect(&self) -> Rect {
+        Rect::new(self.top, self.bottom, self.left, self.right)
+    }
+}
+
 impl Default for MappedEl {
     fn default() -> Self {
         MappedEl {
@@ -63,11 +71,7 @@ impl MappedEl {
 
     pub fn to_js_value(&self) -> JsValue {
         let jsobject = JsObject::new();
-        let target_el = if let Some(el) = self.target_el.clone() {
-            Some(el.dyn_into::<JsValue>().unwrap())
-        } else {
-            None
-        };
+        let target_el = self.target_el.clone().map(|el| el.dyn_into::<JsValue>().unwrap());
         let sections = if let Some(all_sections) = self.section.as_ref() {
             let all_sections = all_sections.borrow().to_js();
             Some(all_sections)
@@ -91,8 +95,8 @@ impl MappedEl {
         jsobject.set_prop(&"section".to_string(), &sections);
         jsobject.set_prop(&"speed".to_string(), &self.speed);
         jsobject.set_prop(&"delay".to_string(), &self.delay);
-        jsobject.set_prop(&"position".to_string(), &self.position);
-        jsobject.set_prop(&"direction".to_string(), &self.direction);
+        jsobject.set_prop(&"position".to_string(), &self.position.map(|position| position.as_str().to_string()));
+        jsobject.set_prop(&"direction".to_string(), &self.direction.map(|direction| direction.as_str().to_string()));
         jsobject.set_prop(&"sticky".to_string(), &self.sticky);
 
 
@@ -122,28 +126,177 @@ impl Els {
     }
 }
 
+#[derive(Default)]
 pub struct ScrollToOption {
     pub offset: Option<String>,
     pub callback: Option<Rc<Box<dyn Fn()>>>,
     pub duration: Option<f64>,
-    pub easing: Option<[f64; 4]>,
+    pub easing: Option<Easing>,
     pub disable_lerp: Option<bool>,
 }
 
-impl Default for ScrollToOption {
-    fn default() -> Self {
-        ScrollToOption {
-            offset: None,
-            callback: None, 
-            duration: None,
-            easing: None,
-            disable_lerp: None,
+impl ScrollToOption {
+    /// Build the option from the object given to `scrollTo`, `undefined` and `null` give the default option.
+    /// Only an `easing` that cannot be parsed is an error, other invalid fields are ignored.
+    pub fn from_js(value: &JsValue) -> Result<Self, ScrollToInterrupt> {
+        if value.is_undefined() || value.is_null() {
+            return Ok(ScrollToOption::default());
         }
+
+        let js_option: JsScrollToOption = match serde_wasm_bindgen::from_value(value.clone()) {
+            Ok(option) => option,
+            Err(_) => {
+                console::warn_1(&"[options] parameter of scrollTo is not valid".into());
+                JsScrollToOption::default()
+            }
+        };
+
+        let callback = match Reflect::get(value, &"callback".into()) {
+            Ok(callback) => callback.dyn_into::<Function>().ok(),
+            Err(_) => None
+        };
+        let callback = callback.map(|function| {
+            let callback: Rc<Box<dyn Fn()>> = Rc::new(Box::new(move || {
+                let _ = function.call0(&JsValue::NULL);
+            }));
+            callback
+        });
+
+        let easing = Reflect::get(value, &"easing".into()).unwrap_or(JsValue::UNDEFINED);
+        let easing = match Easing::from_js(&easing) {
+            Ok(easing) => easing,
+            Err(err) => {
+                console::warn_1(&format!("[easing] option of scrollTo is not valid: {err}").into());
+                return Err(ScrollToInterrupt::InvalidEasing);
+            }
+        };
+
+        Ok(ScrollToOption {
+            offset: js_option.offset.map(|offset| match offset {
+                JsOffset::Num(num) => num.to_string(),
+                JsOffset::String(string) => string,
+            }),
+            callback,
+            duration: js_option.duration,
+            easing,
+            disable_lerp: js_option.disable_lerp,
+        })
     }
 }
 
+#[derive(Deserialize, Default)]
+#[serde(default)]
+#[serde(rename_all = "camelCase")]
+struct JsScrollToOption {
+    offset: Option<JsOffset>,
+    duration: Option<f64>,
+    disable_lerp: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsOffset {
+    Num(f64),
+    String(String),
+}
+
 pub enum ScrollToTarget {
     String(String),
     Element(Element),
     Num(f64),
 }
+
+impl ScrollToTarget {
+    /// Accepts a selector (or `"top"`, `"bottom"`, `"left"`, `"right"`), an `Element` or a number.
+    pub fn from_js(value: JsValue) -> Option<Self> {
+        if let Some(string) = value.as_string() {
+            Some(ScrollToTarget::String(string))
+        } else if let Some(num) = value.as_f64() {
+            Some(ScrollToTarget::Num(num))
+        } else {
+            value.dyn_into::<Element>().ok().map(ScrollToTarget::Element)
+        }
+    }
+}
+
+
+/// Settles the promise returned by `scrollTo`.
+#[derive(Clone, Debug)]
+pub struct ScrollToPromise {
+    resolve: Function,
+    reject: Function,
+}
+
+impl ScrollToPromise {
+    pub fn new() -> (Promise, Self) {
+        let mut settle = None;
+        let promise = Promise::new(&mut |resolve, reject| {
+            settle = Some(ScrollToPromise { resolve, reject });
+        });
+
+        (promise, settle.unwrap())
+    }
+
+    pub fn resolve(&self, position: &Position) {
+        let _ = self.resolve.call1(&JsValue::NULL, &position.to_js());
+    }
+
+    pub fn reject(&self, reason: ScrollToInterrupt) {
+        let _ = self.reject.call1(&JsValue::NULL, &reason.to_js());
+    }
+
+    pub fn rejected(reason: ScrollToInterrupt) -> Promise {
+        Promise::reject(&reason.to_js())
+    }
+}
+
+/// Why a `scrollTo` promise got rejected, exposed as `error.reason`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScrollToInterrupt {
+    Wheel,
+    Scrollbar,
+    Keyboard,
+    Cancelled,
+    Stopped,
+    Destroyed,
+    InvalidTarget,
+    InvalidEasing,
+}
+
+impl ScrollToInterrupt {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScrollToInterrupt::Wheel => "wheel",
+            ScrollToInterrupt::Scrollbar => "scrollbar",
+            ScrollToInterrupt::Keyboard => "keyboard",
+            ScrollToInterrupt::Cancelled => "cancelled",
+            ScrollToInterrupt::Stopped => "stopped",
+            ScrollToInterrupt::Destroyed => "destroyed",
+            ScrollToInterrupt::InvalidTarget => "invalidTarget",
+            ScrollToInterrupt::InvalidEasing => "invalidEasing",
+        }
+    }
+
+    fn message(&self) -> &'static str {
+        match self {
+            ScrollToInterrupt::Wheel => "scrollTo interrupted by a wheel or touch gesture",
+            ScrollToInterrupt::Scrollbar => "scrollTo interrupted by dragging the scrollbar",
+            ScrollToInterrupt::Keyboard => "scrollTo interrupted by a navigation key",
+            ScrollToInterrupt::Cancelled => "scrollTo cancelled by another scrollTo",
+            ScrollToInterrupt::Stopped => "scrollTo cancelled because the scroll got stopped",
+            ScrollToInterrupt::Destroyed => "scrollTo cancelled because the instance got destroyed",
+            ScrollToInterrupt::InvalidTarget => "[target] parameter is not valid",
+            ScrollToInterrupt::InvalidEasing => "[easing] parameter is not valid",
+        }
+    }
+}
+
+impl ToJs for ScrollToInterrupt {
+    fn to_js(&self) -> JsValue {
+        let error = Error::new(self.message());
+        error.set_name("ScrollToInterrupted");
+        let _ = Reflect::set(&error, &"reason".into(), &self.as_str().into());
+
+        error.into()
+    }
+}
@@ -1,4 +1,7 @@
-use wasm_bindgen::JsCast;
+use std::{rc::Rc, cell::RefCell};
+
+use convert_js::{ToJs, __internal::JsObject};
+use wasm_bindgen::{JsCast, JsValue};
 use web_sys::{Element, HtmlElement};
 
 use crate::option::Position;
@@ -10,13 +13,13 @@ pub struct Instance {
     pub scroll: Position,
     pub limit: Position,
     pub delta: Option<Position>,
-    pub current_elements: CurrentElements,
+    pub current_elements: Rc<RefCell<CurrentElements>>,
     pub direction: Option<String>,
     pub speed: Option<f64>,
 }
 
 impl Instance {
-    pub fn new(document_element: &Element, current_elements: CurrentElements) -> Self {
+    pub fn new(document_element: &Element, current_elements: Rc<RefCell<CurrentElements>>) -> Self {
         let html_el = document_element.dyn_ref::<HtmlElement>().unwrap();
         let offset_height = html_el.offset_height();
         let offset_width = html_el.offset_width();
@@ -84,4 +87,19 @@ impl Instance {
             _ => panic!("direction axis not supported")
         }
     }
-}
\ No newline at end of file
+}
+
+impl ToJs for Instance {
+    fn to_js(&self) -> JsValue {
+        let jsobject = JsObject::new();
+
+        jsobject.set_prop(&"scroll", &self.scroll);
+        jsobject.set_prop(&"limit", &self.limit);
+        jsobject.set_prop(&"delta", &self.delta);
+        jsobject.set_prop(&"direction", &self.direction);
+        jsobject.set_prop(&"speed", &self.speed);
+        jsobject.set_prop(&"currentElements", &self.current_elements.borrow().to_js());
+
+        jsobject.into_js_value()
+    }
+}
@@ -1,10 +1,152 @@
+use std::{collections::{HashMap, VecDeque}, rc::Rc, cell::RefCell};
+
+use js_sys::{Function, Array};
+use wasm_bindgen::JsValue;
+use web_sys::{console, Event, EventTarget};
+
+/// Events that can be subscribed to with `on`/`off`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ListenerEvent {
+    Scroll,
+    Call,
+    ScrollStart,
+    ScrollEnd,
+    Resize,
+    ContextChange,
+}
+
+impl ListenerEvent {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "scroll" => Some(ListenerEvent::Scroll),
+            "call" => Some(ListenerEvent::Call),
+            "scrollStart" => Some(ListenerEvent::ScrollStart),
+            "scrollEnd" => Some(ListenerEvent::ScrollEnd),
+            "resize" => Some(ListenerEvent::Resize),
+            "contextChange" => Some(ListenerEvent::ContextChange),
+            _ => None
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Listeners {
-    
+    data: HashMap<ListenerEvent, Vec<Function>>,
 }
 
 impl Listeners {
     pub fn new() -> Listeners {
-        Listeners {  }
+        Listeners { data: HashMap::new() }
+    }
+
+    pub fn add(&mut self, event: ListenerEvent, handler: Function) {
+        self.data.entry(event).or_default().push(handler);
+    }
+
+    pub fn remove(&mut self, event: ListenerEvent, handler: &Function) {
+        if let Some(handlers) = self.data.get_mut(&event) {
+            handlers.retain(|current| current != handler);
+        }
+    }
+
+    /// Call every handler of `event` with `args`.
+    /// The handlers are cloned first, so they can call `on`/`off` themselves.
+    /// Nothing from the core may be borrowed here, the handlers can call any method: go through `Core::flush`.
+    pub fn emit(listeners: &Rc<RefCell<Listeners>>, event: ListenerEvent, args: &Array) {
+        let handlers = match listeners.borrow().data.get(&event) {
+            Some(handlers) => handlers.clone(),
+            None => return,
+        };
+
+        for handler in handlers {
+            if let Err(err) = handler.apply(&JsValue::NULL, args) {
+                console::error_1(&err);
+            }
+        }
+    }
+}
+
+
+/// What is left to emit once the core is not borrowed anymore.
+#[derive(Clone, Debug)]
+pub enum Pending {
+    /// The `on` handlers of an event.
+    Handlers(ListenerEvent, Array),
+    /// A `{namespace}` DOM event.
+    Dom(EventTarget, Event),
+}
+
+/// First in, first out.
+#[derive(Clone, Debug)]
+pub struct Queue<T> {
+    items: VecDeque<T>,
+}
+
+impl<T> Queue<T> {
+    pub fn new() -> Self {
+        Queue { items: VecDeque::new() }
+    }
+
+    pub fn push(&mut self, item: T) {
+        self.items.push_back(item);
+    }
+
+    /// Hand the items to `f` one by one, unless `owner` is borrowed: whoever borrows it flushes later.
+    /// Neither is borrowed while `f` runs, so `f` can borrow `owner` mutably, push and flush again.
+    pub fn flush<O>(queue: &Rc<RefCell<Queue<T>>>, owner: &RefCell<O>, mut f: impl FnMut(T)) {
+        while owner.try_borrow_mut().is_ok() {
+            let item = queue.borrow_mut().items.pop_front();
+            match item {
+                Some(item) => f(item),
+                None => break,
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waits_for_the_owner_to_be_released() {
+        let owner = RefCell::new(0);
+        let queue = Rc::new(RefCell::new(Queue::new()));
+        let mut seen = vec![];
+
+        {
+            let _owner = owner.borrow();
+            queue.borrow_mut().push(1);
+            Queue::flush(&queue, &owner, |item| seen.push(item));
+        }
+        assert!(seen.is_empty());
+
+        Queue::flush(&queue, &owner, |item| seen.push(item));
+        assert_eq!(seen, vec![1]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn handlers_can_reenter() {
+        let owner = Rc::new(RefCell::new(0));
+        let queue = Rc::new(RefCell::new(Queue::new()));
+        let seen = Rc::new(RefCell::new(vec![]));
+        queue.borrow_mut().push(1);
+        queue.borrow_mut().push(2);
+
+        fn handle(item: i32, owner: &Rc<RefCell<i32>>, queue: &Rc<RefCell<Queue<i32>>>, seen: &Rc<RefCell<Vec<i32>>>) {
+            seen.borrow_mut().push(item);
+            //like a handler calling `update`, then `on` which emits again
+            *owner.borrow_mut() += 1;
+            if item == 1 {
+                queue.borrow_mut().push(3);
+                Queue::flush(queue, owner, |item| handle(item, owner, queue, seen));
+            }
+        }
+        Queue::flush(&queue, &owner, |item| handle(item, &owner, &queue, &seen));
+
+        assert_eq!(*seen.borrow(), vec![1, 2, 3]);
+        assert_eq!(*owner.borrow(), 3);
+        assert!(queue.borrow().items.is_empty());
+    }
+}
@@ -1,6 +1,6 @@
 use web_sys::{Element, window};
 
-use crate::option::Position;
+use crate::{option::Position, error::LocomotiveError};
 
 pub mod current_elements;
 pub mod element_type;
@@ -12,9 +12,26 @@ pub fn lerp(start: f64, end: f64, amt: f64) -> f64 {
     (1.0 - amt) * start + amt * end
 }
 
+/// `performance.now()`, the clock of the `requestAnimationFrame` timestamps.
+pub fn now() -> f64 {
+    window().unwrap().performance().unwrap().now()
+}
+
+/// The element matched by the `target` attribute, `el` itself when it is not set.
+pub fn get_target_el(el: &Element, target: Option<String>, name: &str) -> Result<Element, LocomotiveError> {
+    let target = match target {
+        Some(target) => target,
+        None => return Ok(el.clone()),
+    };
+
+    match window().unwrap().document().unwrap().query_selector(&target) {
+        Ok(Some(target_el)) => Ok(target_el),
+        _ => Err(LocomotiveError::InvalidAttribute { name: name.to_string(), value: target, expected: "a selector matching an element".to_string() }),
+    }
+}
 
 pub fn get_translate(el: &Element) -> Position {
-    let style =  window().unwrap().get_computed_style(&el);
+    let style =  window().unwrap().get_computed_style(el);
 
     if let Ok(Some(style)) = style {
         let transform = style.get_property_value("transform").unwrap_or_else(|_| {
@@ -36,7 +53,6 @@ pub fn get_translate(el: &Element) -> Position {
         } else {
             return Position::new(0.0, 0.0)
         }
-    } else {
     }
 
     Position { x: 0.0, y: 0.0 }
@@ -1,52 +1,53 @@
-use convert_js::ToJs;
-use js_sys::Function;
-use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
-use web_sys::window;
+use std::{rc::Rc, cell::RefCell, fmt};
 
-use crate::{option::LocomotiveOption, utils::element_type::ElementType};
+use wasm_bindgen::{prelude::Closure, JsCast};
+use web_sys::{window, EventTarget, WheelEvent, TouchEvent, AddEventListenerOptions};
 
- 
-#[wasm_bindgen(module= "/js/virtual-scroll/index.js")]
-extern "C" {
-    #[derive(Debug, Clone)]
-    pub type VirtualScroll;
+use crate::{option::{LocomotiveOption, Position}, utils::element_type::ElementType};
 
-    #[wasm_bindgen(constructor)]
-    pub fn new(option: JsValue) -> VirtualScroll;
 
-    #[wasm_bindgen(method)]
-    pub fn on(this: &VirtualScroll, callback: &Function);
+/// Pixels per line, for wheels reporting lines outside of Firefox.
+pub const LINE_HEIGHT: f64 = 16.0;
+
+/// Input normalized to pixels. Like `virtual-scroll`, a positive delta moves back towards the start.
+#[derive(Clone, Debug)]
+pub struct VsEvent {
+    pub delta_x: f64,
+    pub delta_y: f64,
 }
 
+/// Handler given to `VirtualScroll::on`.
+pub struct VsCallback(pub Box<dyn Fn(&VsEvent)>);
+
+impl fmt::Debug for VsCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("VsCallback")
+    }
+}
 
-#[derive(Clone, ToJs)]
+type Handler = Rc<RefCell<Option<VsCallback>>>;
+
+
+#[derive(Clone, Debug)]
 pub struct VsOption {
-    pub el: JsValue,
+    pub el: EventTarget,
     pub mouse_multiplier: f64,
     pub firefox_multiplier: f64,
     pub touch_multiplier: f64,
-    pub use_keyboard: bool,
     pub passive: bool,
 }
 
 
 impl VsOption {
-    pub fn _new(options: &LocomotiveOption) -> Self {
+    pub fn new(options: &LocomotiveOption) -> Self {
         let window = window().unwrap();
 
-        let element: JsValue = match options.scroll_from_anywhere {
-            true => {
-                let document = window.document().unwrap();
-                AsRef::<JsValue>::as_ref(&document).clone()
-            },
+        let el: EventTarget = match options.scroll_from_anywhere {
+            true => window.document().unwrap().into(),
             false => {
                 match &options.el {
-                    ElementType::Document(doc) => {
-                        AsRef::<JsValue>::as_ref(doc).clone()
-                    },
-                    ElementType::Element(el) => {
-                        AsRef::<JsValue>::as_ref(el).clone()
-                    }
+                    ElementType::Document(doc) => doc.clone().into(),
+                    ElementType::Element(el) => el.clone().into(),
                 }
             }
         };
@@ -57,13 +58,150 @@ impl VsOption {
         };
 
         Self {
-            el: element,
+            el,
             mouse_multiplier,
             firefox_multiplier: options.firefox_multiplier,
             touch_multiplier: options.touch_multiplier,
-            use_keyboard: false,
             passive: true
         }
-        
+
+    }
+}
+
+
+/// Wheel and touch listeners turned into a single stream of `VsEvent`.
+#[derive(Clone, Debug)]
+pub struct VirtualScroll {
+    options: VsOption,
+    handlers: Rc<RefCell<Vec<Handler>>>,
+    touch_start: Rc<RefCell<Option<Position>>>,
+
+    on_wheel: Rc<RefCell<Option<Closure<dyn Fn(WheelEvent)>>>>,
+    on_touch_start: Rc<RefCell<Option<Closure<dyn Fn(TouchEvent)>>>>,
+    on_touch_move: Rc<RefCell<Option<Closure<dyn Fn(TouchEvent)>>>>,
+}
+
+impl VirtualScroll {
+    pub fn new(options: VsOption) -> Self {
+        let mut vs = VirtualScroll {
+            options,
+            handlers: Rc::new(RefCell::new(vec![])),
+            touch_start: Rc::new(RefCell::new(None)),
+
+            on_wheel: Rc::new(RefCell::new(None)),
+            on_touch_start: Rc::new(RefCell::new(None)),
+            on_touch_move: Rc::new(RefCell::new(None)),
+        };
+
+        vs.on_wheel_cb();
+        vs.on_touch_start_cb();
+        vs.on_touch_move_cb();
+        vs.bind();
+
+        vs
+    }
+
+    pub fn on(&self, handler: Handler) {
+        self.handlers.borrow_mut().push(handler);
+    }
+
+    /// Remove the listeners and forget the handlers.
+    pub fn destroy(&self) {
+        let el = &self.options.el;
+        if let Some(cb) = self.on_wheel.borrow_mut().take() {
+            let _ = el.remove_event_listener_with_callback("wheel", cb.as_ref().unchecked_ref());
+        }
+        if let Some(cb) = self.on_touch_start.borrow_mut().take() {
+            let _ = el.remove_event_listener_with_callback("touchstart", cb.as_ref().unchecked_ref());
+        }
+        if let Some(cb) = self.on_touch_move.borrow_mut().take() {
+            let _ = el.remove_event_listener_with_callback("touchmove", cb.as_ref().unchecked_ref());
+        }
+
+        self.handlers.borrow_mut().clear();
+    }
+
+    fn bind(&self) {
+        let el = &self.options.el;
+        let listener_options = AddEventListenerOptions::new();
+        listener_options.set_passive(self.options.passive);
+
+        if let Some(cb) = self.on_wheel.borrow().as_ref() {
+            el.add_event_listener_with_callback_and_add_event_listener_options("wheel", cb.as_ref().unchecked_ref(), &listener_options).unwrap();
+        }
+        if let Some(cb) = self.on_touch_start.borrow().as_ref() {
+            el.add_event_listener_with_callback_and_add_event_listener_options("touchstart", cb.as_ref().unchecked_ref(), &listener_options).unwrap();
+        }
+        if let Some(cb) = self.on_touch_move.borrow().as_ref() {
+            el.add_event_listener_with_callback_and_add_event_listener_options("touchmove", cb.as_ref().unchecked_ref(), &listener_options).unwrap();
+        }
+    }
+
+    /// The handlers are cloned first, so they can be added or removed while notified.
+    fn notify(handlers: &Rc<RefCell<Vec<Handler>>>, event: VsEvent) {
+        let handlers = handlers.borrow().clone();
+        for handler in handlers {
+            if let Some(cb) = handler.borrow().as_ref() {
+                (cb.0)(&event);
+            }
+        }
+    }
+
+    fn is_firefox() -> bool {
+        window().unwrap().navigator().user_agent().map(|ua| ua.to_lowercase().contains("firefox")).unwrap_or(false)
+    }
+}
+
+
+//CLOSURES
+impl VirtualScroll {
+    fn on_wheel_cb(&mut self) {
+        let handlers = self.handlers.clone();
+        let options = self.options.clone();
+        let is_firefox = VirtualScroll::is_firefox();
+
+        *self.on_wheel.borrow_mut() = Some(Closure::new(move |event: WheelEvent| {
+            //`deltaMode` is per event: pixels, lines or pages
+            let scale = match event.delta_mode() {
+                WheelEvent::DOM_DELTA_LINE => if is_firefox { options.firefox_multiplier } else { LINE_HEIGHT },
+                WheelEvent::DOM_DELTA_PAGE => window().unwrap().inner_height().unwrap().as_f64().unwrap(),
+                _ => 1.0
+            } * options.mouse_multiplier;
+
+            VirtualScroll::notify(&handlers, VsEvent {
+                delta_x: -event.delta_x() * scale,
+                delta_y: -event.delta_y() * scale,
+            });
+        }));
+    }
+
+    fn on_touch_start_cb(&mut self) {
+        let touch_start = self.touch_start.clone();
+
+        *self.on_touch_start.borrow_mut() = Some(Closure::new(move |event: TouchEvent| {
+            *touch_start.borrow_mut() = event.target_touches().get(0).map(|touch| Position::new(touch.page_x() as f64, touch.page_y() as f64));
+        }));
+    }
+
+    fn on_touch_move_cb(&mut self) {
+        let handlers = self.handlers.clone();
+        let touch_start = self.touch_start.clone();
+        let touch_multiplier = self.options.touch_multiplier;
+
+        *self.on_touch_move.borrow_mut() = Some(Closure::new(move |event: TouchEvent| {
+            let touch = match event.target_touches().get(0) {
+                Some(touch) => Position::new(touch.page_x() as f64, touch.page_y() as f64),
+                None => return
+            };
+            let start = match touch_start.replace(Some(touch.clone())) {
+                Some(start) => start,
+                None => return
+            };
+
+            VirtualScroll::notify(&handlers, VsEvent {
+                delta_x: (touch.x - start.x) * touch_multiplier,
+                delta_y: (touch.y - start.y) * touch_multiplier,
+            });
+        }));
     }
 }